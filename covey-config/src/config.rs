@@ -2,10 +2,10 @@
 
 use std::collections::HashMap;
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, de};
 
 use crate::{
-    hotkey::{Hotkey, KeyCode},
+    hotkey::{Hotkey, HotkeySequence, KeyCode, KeyMode},
     keyed_list::{Id, Identify, KeyedList},
 };
 
@@ -29,12 +29,81 @@ pub struct AppConfig {
     ///
     /// Default is Ctrl+R.
     #[serde(default = "default_reload_hotkey")]
-    pub reload_hotkey: Hotkey,
+    pub reload_hotkey: HotkeySequence,
+    /// System-wide hotkey to show or hide covey.
+    ///
+    /// This works while covey is hidden. Not set by default, as most
+    /// desktops already use the common launcher hotkeys.
+    #[serde(default)]
+    pub toggle_hotkey: Option<Hotkey>,
     /// List of icon themes to use when rendering a named icon from a plugin.
     ///
     /// Icons will try to be loaded from top to bottom.
     #[serde(default = "default_icon_themes")]
     pub icon_themes: Vec<IconTheme>,
+    /// Command used to open a terminal emulator for plugins that run
+    /// commands in a terminal.
+    ///
+    /// The `{}` argument is replaced by the command to run and it's
+    /// arguments, e.g. `"alacritty -e {}"`. If there is no `{}`, the
+    /// command is appended to the end. Arguments with spaces can be
+    /// quoted like in a shell.
+    ///
+    /// If this is not set, the `$TERMINAL` environment variable or a
+    /// common terminal emulator is used.
+    #[serde(default)]
+    pub terminal: Option<String>,
+    /// Environment variables that are removed from commands run by
+    /// plugins, so that they don't inherit covey's environment.
+    ///
+    /// A trailing `*` matches any variable starting with the rest of
+    /// the name, e.g. `"TAURI_*"`.
+    #[serde(default = "default_strip_env_vars")]
+    pub strip_env_vars: Vec<String>,
+    /// How results are ordered when several plugins have the same prefix.
+    #[serde(default)]
+    pub frecency: FrecencyConfig,
+}
+
+/// Ranking of results by how frequently and recently they were activated.
+///
+/// This only applies when the results of several plugins are shown
+/// together, as each plugin ranks its own results.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "kebab-case")]
+pub struct FrecencyConfig {
+    /// Number of days until an activation counts half as much as one
+    /// that just happened.
+    ///
+    /// Default is 7. This must be more than 0.
+    #[serde(
+        default = "default_half_life_days",
+        deserialize_with = "half_life_days"
+    )]
+    pub half_life_days: f64,
+    /// Multipliers of the scores of each plugin's results, to prefer
+    /// some plugins over others.
+    ///
+    /// Plugins that aren't listed have a boost of 1.
+    #[serde(default)]
+    pub plugin_boosts: HashMap<Id, f64>,
+}
+
+impl FrecencyConfig {
+    /// The boost of a plugin's results.
+    pub fn boost(&self, plugin: &Id) -> f64 {
+        self.plugin_boosts.get(plugin).copied().unwrap_or(1.0)
+    }
+}
+
+impl Default for FrecencyConfig {
+    fn default() -> Self {
+        Self {
+            half_life_days: default_half_life_days(),
+            plugin_boosts: HashMap::new(),
+        }
+    }
 }
 
 /// A theme to try render a named icon with.
@@ -67,18 +136,42 @@ impl Default for AppConfig {
     fn default() -> Self {
         Self {
             reload_hotkey: default_reload_hotkey(),
+            toggle_hotkey: None,
             icon_themes: default_icon_themes(),
+            terminal: None,
+            strip_env_vars: default_strip_env_vars(),
+            frecency: FrecencyConfig::default(),
         }
     }
 }
 
-fn default_reload_hotkey() -> Hotkey {
-    Hotkey {
+fn default_reload_hotkey() -> HotkeySequence {
+    HotkeySequence::from(Hotkey {
         key: KeyCode::R,
         ctrl: true,
         alt: false,
         shift: false,
         meta: false,
+        mode: KeyMode::Logical,
+    })
+}
+
+fn default_half_life_days() -> f64 {
+    7.0
+}
+
+fn half_life_days<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let days = f64::deserialize(deserializer)?;
+    if days > 0.0 {
+        Ok(days)
+    } else {
+        Err(de::Error::invalid_value(
+            de::Unexpected::Float(days),
+            &"a half life of more than 0 days",
+        ))
     }
 }
 
@@ -89,6 +182,21 @@ fn default_icon_themes() -> Vec<IconTheme> {
     }]
 }
 
+fn default_strip_env_vars() -> Vec<String> {
+    [
+        "RUST_LOG",
+        "RUST_BACKTRACE",
+        "RUST_LIB_BACKTRACE",
+        "TAURI_*",
+        "WEBKIT_*",
+        "GDK_BACKEND",
+        "GIO_LAUNCHED_DESKTOP_FILE*",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "kebab-case")]
@@ -97,8 +205,18 @@ pub struct PluginConfig {
     pub prefix: String,
     #[serde(default)] // empty table if missing
     pub config: serde_json::Map<String, serde_json::Value>,
+    /// Hotkeys that the user set for commands, replacing the
+    /// command's default hotkey.
+    #[serde(default)]
+    pub commands: HashMap<Id, HotkeySequence>,
+    /// IDs of commands that must always be confirmed before activating,
+    /// in addition to the commands the plugin requires to be confirmed.
+    #[serde(default)]
+    pub confirm_commands: Vec<Id>,
+    /// System-wide hotkey to show covey with this plugin's prefix
+    /// already typed in.
     #[serde(default)]
-    pub commands: HashMap<Id, Hotkey>,
+    pub open_hotkey: Option<Hotkey>,
 }
 
 impl Identify for PluginConfig {
@@ -106,3 +224,30 @@ impl Identify for PluginConfig {
         &self.id
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::FrecencyConfig;
+
+    #[test]
+    #[expect(clippy::float_cmp, reason = "the values are parsed exactly")]
+    fn half_life_must_be_positive() {
+        let config =
+            |days: &str| toml::from_str::<FrecencyConfig>(&format!("half-life-days = {days}"));
+
+        assert_eq!(config("0.5").unwrap().half_life_days, 0.5);
+        assert_eq!(
+            toml::from_str::<FrecencyConfig>("").unwrap().half_life_days,
+            7.0
+        );
+        for days in ["0.0", "-1.0", "nan"] {
+            let error = config(days).unwrap_err();
+            assert!(
+                error
+                    .to_string()
+                    .contains("a half life of more than 0 days"),
+                "{error}"
+            );
+        }
+    }
+}
@@ -0,0 +1,364 @@
+//! Finding hotkeys that can never be activated, because another hotkey is
+//! activated first or the launcher uses them itself.
+
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::GlobalConfig,
+    hotkey::{Hotkey, HotkeySequence},
+    keyed_list::Id,
+    manifest::PluginManifest,
+};
+
+/// A hotkey that can never be activated.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "kebab-case", tag = "kind")]
+pub enum HotkeyConflict {
+    /// Another hotkey is activated instead.
+    Hidden {
+        /// The hotkey that can never be activated.
+        shadowed: HotkeyBinding,
+        /// The hotkey that is activated instead.
+        ///
+        /// This is the same as the shadowed hotkey, or the start of its
+        /// sequence.
+        by: HotkeyBinding,
+    },
+    /// The hotkey starts with a [reserved](HotkeySequence::is_reserved)
+    /// key, which the launcher handles itself.
+    Reserved { shadowed: HotkeyBinding },
+}
+
+impl HotkeyConflict {
+    /// The hotkey that can never be activated.
+    pub fn shadowed(&self) -> &HotkeyBinding {
+        match self {
+            Self::Hidden { shadowed, .. } | Self::Reserved { shadowed } => shadowed,
+        }
+    }
+}
+
+/// A hotkey with the thing that set it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "kebab-case")]
+pub struct HotkeyBinding {
+    pub hotkey: HotkeySequence,
+    pub source: HotkeySource,
+}
+
+/// Where a hotkey is set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "kebab-case", tag = "kind")]
+pub enum HotkeySource {
+    /// [`AppConfig::reload_hotkey`](crate::config::AppConfig::reload_hotkey).
+    Reload,
+    /// A command in a plugin's manifest.
+    Command {
+        plugin: Id,
+        command: Id,
+        /// Whether the hotkey was set by the user, instead of being the
+        /// command's default hotkey.
+        user: bool,
+    },
+}
+
+/// Finds every hotkey in the menu that is hidden by another hotkey.
+///
+/// `manifest` returns the manifest of each plugin in the config. Plugins
+/// without a manifest are skipped.
+///
+/// Each plugin's commands are checked against each other and against
+/// the reload hotkey. A hotkey is hidden by an earlier one that is the
+/// same or starts its sequence, as that is activated as soon as it's
+/// pressed. The reload hotkey comes before every command, and commands
+/// are in the order of the manifest.
+///
+/// Keys are compared regardless of their [mode](crate::hotkey::KeyMode),
+/// as a physical key and the logical key with the same name are usually
+/// the same key.
+///
+/// Commands of different plugins never clash, and neither do commands
+/// that are never on the same list item, but this can't know which
+/// commands the plugin puts on an item, so every command is checked.
+///
+/// Reserved hotkeys are reported on their own, and aren't checked
+/// against other hotkeys, as they never activate anything.
+pub fn find_conflicts<'a>(
+    config: &GlobalConfig,
+    manifest: impl Fn(&Id) -> Option<&'a PluginManifest>,
+) -> Vec<HotkeyConflict> {
+    let reload = HotkeyBinding {
+        hotkey: config.app.reload_hotkey.clone(),
+        source: HotkeySource::Reload,
+    };
+
+    let mut conflicts = Vec::new();
+    if reload.hotkey.is_reserved() {
+        conflicts.push(HotkeyConflict::Reserved {
+            shadowed: reload.clone(),
+        });
+    }
+
+    for plugin in config.plugins.iter() {
+        let Some(manifest) = manifest(&plugin.id) else {
+            continue;
+        };
+
+        let commands = manifest.commands.iter().filter_map(|command| {
+            let user_hotkey = plugin.commands.get(&command.id);
+            let hotkey = user_hotkey.or(command.default_hotkey.as_ref())?;
+            Some(HotkeyBinding {
+                hotkey: hotkey.clone(),
+                source: HotkeySource::Command {
+                    plugin: plugin.id.clone(),
+                    command: command.id.clone(),
+                    user: user_hotkey.is_some(),
+                },
+            })
+        });
+        let (reserved, commands): (Vec<_>, Vec<_>) =
+            commands.partition(|binding| binding.hotkey.is_reserved());
+        conflicts.extend(
+            reserved
+                .into_iter()
+                .map(|shadowed| HotkeyConflict::Reserved { shadowed }),
+        );
+
+        let bindings: Vec<_> = std::iter::once(&reload)
+            .filter(|reload| !reload.hotkey.is_reserved())
+            .chain(&commands)
+            .collect();
+
+        for (i, earlier) in bindings.iter().enumerate() {
+            for later in &bindings[i + 1..] {
+                let (shadowed, by) = if starts_with(&later.hotkey, &earlier.hotkey) {
+                    (later, earlier)
+                } else if starts_with(&earlier.hotkey, &later.hotkey) {
+                    (earlier, later)
+                } else {
+                    continue;
+                };
+                conflicts.push(HotkeyConflict::Hidden {
+                    shadowed: (*shadowed).clone(),
+                    by: (*by).clone(),
+                });
+            }
+        }
+    }
+
+    conflicts
+}
+
+/// Whether `sequence` starts with every hotkey of `prefix`, ignoring their
+/// modes.
+fn starts_with(sequence: &HotkeySequence, prefix: &HotkeySequence) -> bool {
+    let same_key = |a: &Hotkey, b: &Hotkey| {
+        a.key == b.key
+            && a.ctrl == b.ctrl
+            && a.alt == b.alt
+            && a.shift == b.shift
+            && a.meta == b.meta
+    };
+
+    let (sequence, prefix) = (sequence.hotkeys(), prefix.hotkeys());
+    sequence.len() >= prefix.len() && sequence.iter().zip(prefix).all(|(a, b)| same_key(a, b))
+}
+
+impl fmt::Display for HotkeyConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hidden { shadowed, by } => write!(
+                f,
+                "{} for {} is hidden by {} for {}",
+                shadowed.hotkey, shadowed.source, by.hotkey, by.source
+            ),
+            Self::Reserved { shadowed } => write!(
+                f,
+                "{} for {} starts with a key the launcher uses",
+                shadowed.hotkey, shadowed.source
+            ),
+        }
+    }
+}
+
+impl fmt::Display for HotkeySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Reload => write!(f, "reloading the plugin"),
+            Self::Command {
+                plugin, command, ..
+            } => write!(
+                f,
+                "command {:?} of plugin {:?}",
+                command.as_str(),
+                plugin.as_str()
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HotkeyConflict, HotkeySource, find_conflicts};
+    use crate::{config::GlobalConfig, keyed_list::Id, manifest::PluginManifest};
+
+    fn manifest() -> PluginManifest {
+        PluginManifest::try_from_toml(
+            r#"
+            name = "Test"
+
+            [[commands]]
+            id = "open"
+            title = "Open"
+            default-hotkey = "enter"
+
+            [[commands]]
+            id = "copy"
+            title = "Copy"
+            default-hotkey = "ctrl+c"
+
+            [[commands]]
+            id = "copy-path"
+            title = "Copy path"
+            default-hotkey = "ctrl+c ctrl+p"
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn config(plugin: &str) -> GlobalConfig {
+        toml::from_str(&format!(
+            "[[plugins]]\nid = \"test\"\nprefix = \"t\"\n{plugin}"
+        ))
+        .unwrap()
+    }
+
+    fn source(command: &str, user: bool) -> HotkeySource {
+        HotkeySource::Command {
+            plugin: Id::new("test"),
+            command: Id::new(command),
+            user,
+        }
+    }
+
+    /// The sources of the shadowed and hiding hotkeys.
+    fn hidden(conflict: &HotkeyConflict) -> (&HotkeySource, &HotkeySource) {
+        match conflict {
+            HotkeyConflict::Hidden { shadowed, by } => (&shadowed.source, &by.source),
+            HotkeyConflict::Reserved { .. } => panic!("{conflict} isn't hidden"),
+        }
+    }
+
+    #[test]
+    fn prefix_hides_longer_sequence() {
+        let manifest = manifest();
+        let conflicts = find_conflicts(&config(""), |_| Some(&manifest));
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(
+            hidden(&conflicts[0]),
+            (&source("copy-path", false), &source("copy", false))
+        );
+    }
+
+    #[test]
+    fn user_hotkeys_replace_defaults() {
+        let manifest = manifest();
+        let conflicts = find_conflicts(
+            &config("commands.copy = \"alt+c\"\ncommands.open = \"ctrl+c ctrl+p\""),
+            |_| Some(&manifest),
+        );
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(
+            hidden(&conflicts[0]),
+            (&source("copy-path", false), &source("open", true))
+        );
+        assert_eq!(
+            conflicts[0].to_string(),
+            "Ctrl+C Ctrl+P for command \"copy-path\" of plugin \"test\" \
+            is hidden by Ctrl+C Ctrl+P for command \"open\" of plugin \"test\""
+        );
+    }
+
+    #[test]
+    fn reload_hotkey_hides_commands() {
+        let manifest = manifest();
+        let mut config = config("commands.copy-path = \"alt+r\"");
+        config.app.reload_hotkey = "ctrl+c".parse().unwrap();
+        let conflicts = find_conflicts(&config, |_| Some(&manifest));
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(
+            hidden(&conflicts[0]),
+            (&source("copy", false), &HotkeySource::Reload)
+        );
+    }
+
+    #[test]
+    fn shorter_later_hotkey_hides_earlier_sequence() {
+        let manifest = manifest();
+        let conflicts = find_conflicts(
+            &config("commands.open = \"ctrl+k ctrl+o\"\ncommands.copy-path = \"ctrl+k\""),
+            |_| Some(&manifest),
+        );
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(
+            hidden(&conflicts[0]),
+            (&source("open", true), &source("copy-path", true))
+        );
+    }
+
+    #[test]
+    fn modes_are_ignored_in_prefixes() {
+        let manifest = manifest();
+        let conflicts = find_conflicts(
+            &config("commands.open = \"physical+ctrl+c\"\ncommands.copy-path = \"alt+p\""),
+            |_| Some(&manifest),
+        );
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(
+            hidden(&conflicts[0]),
+            (&source("copy", false), &source("open", true))
+        );
+    }
+
+    #[test]
+    fn reserved_hotkeys_are_reported() {
+        let manifest = manifest();
+        let mut config = config("commands.open = \"escape\"\ncommands.copy = \"escape ctrl+c\"");
+        config.app.reload_hotkey = "space".parse().unwrap();
+        let conflicts = find_conflicts(&config, |_| Some(&manifest));
+
+        let reserved: Vec<_> = conflicts
+            .iter()
+            .filter(|conflict| matches!(conflict, HotkeyConflict::Reserved { .. }))
+            .map(|conflict| &conflict.shadowed().source)
+            .collect();
+        assert_eq!(
+            reserved,
+            [
+                &HotkeySource::Reload,
+                &source("open", true),
+                &source("copy", true)
+            ]
+        );
+        assert_eq!(
+            conflicts[0].to_string(),
+            "Space for reloading the plugin starts with a key the launcher uses"
+        );
+        assert_eq!(conflicts.len(), 3, "reserved hotkeys don't hide others");
+    }
+
+    #[test]
+    fn plugins_without_manifests_are_skipped() {
+        assert!(find_conflicts(&config(""), |_| None).is_empty());
+    }
+}
@@ -4,7 +4,7 @@
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::quote;
 
-use super::CratePaths;
+use super::{CratePaths, generate_types::command_params_module};
 use crate::{keyed_list::Identify, manifest::PluginManifest};
 
 pub(super) fn generate_ext_trait(manifest: &PluginManifest, paths: &CratePaths) -> TokenStream {
@@ -16,11 +16,15 @@ pub(super) fn generate_ext_trait(manifest: &PluginManifest, paths: &CratePaths)
         .iter()
         .map(|key| {
             let method = Ident::new(&format!("on_{}", key.id().as_str().replace('-', "_")), Span::call_site());
+            let params = (!key.parameters.is_empty()).then(|| {
+                let module = command_params_module(key.id().as_str());
+                quote! { self::command_params::#module::Params }
+            });
 
             quote! {
                 fn #method<Fut, R>(
                     self,
-                    callback: impl Fn() -> Fut + ::core::marker::Send + ::core::marker::Sync + 'static
+                    callback: impl Fn(#params) -> Fut + ::core::marker::Send + ::core::marker::Sync + 'static
                 ) -> Self
                 where
                     Fut: ::core::future::Future<Output = #ret>
@@ -37,7 +41,32 @@ pub(super) fn generate_ext_trait(manifest: &PluginManifest, paths: &CratePaths)
     };
 
     let ext_impl_ty = &paths.ext_impl_ty;
-    let command_names = manifest.commands.iter().map(|item| item.id().as_str());
+    let bodies = manifest.commands.iter().map(|item| {
+        let command_name = item.id().as_str();
+        if item.parameters.is_empty() {
+            quote! {
+                let callback = ::std::sync::Arc::new(callback);
+                self.add_command(
+                    #command_name,
+                    ::std::sync::Arc::new(move || ::std::boxed::Box::pin({
+                        let callback = ::std::sync::Arc::clone(&callback);
+                        async move { callback().await.map(::core::convert::Into::into) }
+                    }))
+                )
+            }
+        } else {
+            quote! {
+                let callback = ::std::sync::Arc::new(callback);
+                self.add_command_with_params(
+                    #command_name,
+                    move |params| {
+                        let callback = ::std::sync::Arc::clone(&callback);
+                        async move { callback(params).await.map(::core::convert::Into::into) }
+                    }
+                )
+            }
+        }
+    });
     let trait_impl = quote! {
         impl self::CommandExt for #ext_impl_ty {
             #(
@@ -46,15 +75,11 @@ pub(super) fn generate_ext_trait(manifest: &PluginManifest, paths: &CratePaths)
                 /// The closure can return any type that implements [`Into<Actions>`].
                 /// This includes `impl IntoIterator<Item = Action>`, a single [`Action`],
                 /// or an [`Input`].
+                ///
+                /// If the command has parameters, the closure is called with
+                /// the values that the user entered.
                 #signatures {
-                    let callback = ::std::sync::Arc::new(callback);
-                    self.add_command(
-                        #command_names,
-                        ::std::sync::Arc::new(move || ::std::boxed::Box::pin({
-                            let callback = ::std::sync::Arc::clone(&callback);
-                            async move { callback().await.map(::core::convert::Into::into) }
-                        }))
-                    )
+                    #bodies
                 }
             )*
         }
@@ -26,8 +26,51 @@ pub(super) fn generate_types(manifest: &PluginManifest, paths: &CratePaths) -> T
         paths,
         &Ident::new("config", Span::call_site()),
     );
+    let config_types = field.extras;
+
+    // each command gets it's own module so that the field modules
+    // don't conflict.
+    let command_modules = manifest
+        .commands
+        .iter()
+        .filter(|command| !command.parameters.is_empty())
+        .map(|command| {
+            let module = command_params_module(command.id().as_str());
+            let params = FieldType::from_struct(
+                SchemaStruct {
+                    fields: command
+                        .parameters
+                        .iter()
+                        .map(|val| (val.id().as_str().to_owned(), val.r#type.clone()))
+                        .collect(),
+                },
+                paths,
+                &Ident::new("params", Span::call_site()),
+            )
+            .extras;
+
+            quote! {
+                pub mod #module {
+                    #params
+                }
+            }
+        });
+
+    quote! {
+        #config_types
+
+        /// Parameters of each command that has any, in a module with the
+        /// command's name.
+        pub mod command_params {
+            #(#command_modules)*
+        }
+    }
+}
 
-    field.extras
+/// Name of the module within `command_params` that has the parameters
+/// of a command.
+pub(super) fn command_params_module(command_id: &str) -> Ident {
+    Ident::new_raw(&command_id.replace('-', "_"), Span::call_site())
 }
 
 struct FieldType {
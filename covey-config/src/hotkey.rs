@@ -1,9 +1,12 @@
 use core::fmt;
-use std::str::FromStr;
+use std::{
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, de::Error as _};
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
 #[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "kebab-case")]
 pub struct Hotkey {
@@ -16,18 +19,158 @@ pub struct Hotkey {
     pub shift: bool,
     #[serde(default)]
     pub meta: bool,
+    /// Whether [`Hotkey::key`] is the character typed or the key's
+    /// position.
+    #[serde(default)]
+    pub mode: KeyMode,
+}
+
+impl Hotkey {
+    /// Whether the launcher uses this hotkey itself, so it can't activate
+    /// a command.
+    ///
+    /// These are the keys that move through the list, edit the query or
+    /// close the window, when pressed without ctrl, alt or meta.
+    pub fn is_reserved(&self) -> bool {
+        !self.ctrl && !self.alt && !self.meta && self.key.is_reserved()
+    }
+
+    /// Whether pressing `press` activates this hotkey.
+    ///
+    /// The key is compared with the logical or physical key of the press,
+    /// depending on [`Hotkey::mode`]. Modifiers must be exactly the same.
+    pub fn matches(&self, press: &KeyPress) -> bool {
+        let key = match self.mode {
+            KeyMode::Logical => press.logical.as_ref(),
+            KeyMode::Physical => press.physical.as_ref(),
+        };
+        key == Some(&self.key)
+            && self.ctrl == press.ctrl
+            && self.alt == press.alt
+            && self.shift == press.shift
+            && self.meta == press.meta
+    }
+}
+
+impl<'de> Deserialize<'de> for Hotkey {
+    /// Like the derived implementation, but a [`KeyCode::Char`] can't be
+    /// matched in [`KeyMode::Physical`].
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "kebab-case")]
+        #[expect(clippy::struct_excessive_bools, reason = "same fields as Hotkey")]
+        struct Fields {
+            key: KeyCode,
+            #[serde(default)]
+            ctrl: bool,
+            #[serde(default)]
+            alt: bool,
+            #[serde(default)]
+            shift: bool,
+            #[serde(default)]
+            meta: bool,
+            #[serde(default)]
+            mode: KeyMode,
+        }
+
+        let Fields {
+            key,
+            ctrl,
+            alt,
+            shift,
+            meta,
+            mode,
+        } = Fields::deserialize(deserializer)?;
+        if let (KeyCode::Char(c), KeyMode::Physical) = (&key, mode) {
+            return Err(D::Error::custom(ParseAcceleratorError::NoPhysicalKey(
+                c.to_string(),
+            )));
+        }
+
+        Ok(Self {
+            key,
+            ctrl,
+            alt,
+            shift,
+            meta,
+            mode,
+        })
+    }
 }
 
-/// A single key on a standard US QWERTY keyboard without shift being held.
+/// How the key of a [`Hotkey`] is matched.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyMode {
+    /// The key is the character that is typed, in whatever keyboard
+    /// layout the user has.
+    ///
+    /// For example, `ctrl+z` is the key labelled Z on both a QWERTY and
+    /// an AZERTY keyboard. Keys that don't type a character, like enter
+    /// or F1, are the same in both modes.
+    #[default]
+    Logical,
+    /// The key is a position on the keyboard, named after the key at that
+    /// position on a US QWERTY keyboard.
+    ///
+    /// For example, `ctrl+z` is the key to the right of left shift, which
+    /// is labelled W on an AZERTY keyboard. This can't be used with
+    /// [`KeyCode::Char`].
+    Physical,
+}
+
+/// A key that was pressed, to be matched against [`Hotkey`]s.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(rename_all = "kebab-case")]
+#[expect(clippy::struct_excessive_bools, reason = "these are the modifier keys")]
+pub struct KeyPress {
+    /// The key at the same position on a US QWERTY keyboard.
+    ///
+    /// This is [`None`] if there is no such key.
+    pub physical: Option<KeyCode>,
+    /// The character that the key typed, or the name of the key if it
+    /// doesn't type one.
+    ///
+    /// This is [`None`] if it isn't a [`KeyCode`].
+    pub logical: Option<KeyCode>,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub meta: bool,
+}
+
+impl From<Hotkey> for KeyPress {
+    /// A press that matches `hotkey`, and the same hotkey in the other
+    /// [`KeyMode`] if there is one.
+    fn from(hotkey: Hotkey) -> Self {
+        let physical = match hotkey.key {
+            KeyCode::Char(_) => None,
+            ref key => Some(key.clone()),
+        };
+        Self {
+            physical,
+            logical: Some(hotkey.key),
+            ctrl: hotkey.ctrl,
+            alt: hotkey.alt,
+            shift: hotkey.shift,
+            meta: hotkey.meta,
+        }
+    }
+}
+
+/// A single key on a standard US QWERTY keyboard without shift being held,
+/// or any other character.
 ///
 /// Does **NOT** include:
 /// - Modifiers
-/// - Text editing keys like backspace / delete / insert.
-/// - Movement keys like page up / home / down arrow.
-/// - Escape or lock keys.
+/// - Lock keys.
 /// - Media keys.
+///
+/// This deserializes from a string, parsed with [`KeyCode::from_str`].
 #[rustfmt::skip]
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
 #[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "kebab-case")]
 pub enum KeyCode {
@@ -51,6 +194,42 @@ pub enum KeyCode {
     LeftBracket, RightBracket, Backslash,
     Semicolon, Apostrophe, Enter,
     Comma, Period, Slash,
+    Escape, Space,
+    Backspace, Delete, Insert,
+    ArrowUp, ArrowDown, ArrowLeft, ArrowRight,
+    Home, End, PageUp, PageDown,
+    Numpad0, Numpad1, Numpad2,
+    Numpad3, Numpad4, Numpad5,
+    Numpad6, Numpad7, Numpad8,
+    Numpad9,
+    NumpadAdd, NumpadSubtract, NumpadMultiply,
+    NumpadDivide, NumpadDecimal, NumpadEnter,
+    /// Any other character, which is always lowercase.
+    ///
+    /// This is for keys that aren't on a US QWERTY keyboard, like `é` or
+    /// `ж`, so it only matches in [`KeyMode::Logical`]. Characters that
+    /// are on a US QWERTY keyboard are always one of the other keys.
+    #[serde(untagged)]
+    Char(char),
+}
+
+impl KeyCode {
+    /// See [`Hotkey::is_reserved`].
+    fn is_reserved(&self) -> bool {
+        matches!(
+            self,
+            Self::Escape
+                | Self::Space
+                | Self::Backspace
+                | Self::Delete
+                | Self::ArrowUp
+                | Self::ArrowDown
+                | Self::ArrowLeft
+                | Self::ArrowRight
+                | Self::Home
+                | Self::End
+        )
+    }
 }
 
 // FromStr and Display implementations //
@@ -67,6 +246,9 @@ pub enum ParseAcceleratorError {
     UnknownModifier(String),
     /// Unknown key.
     UnknownKey(String),
+    /// A key that isn't on a US QWERTY keyboard was given with
+    /// "physical", so it has no position to match.
+    NoPhysicalKey(String),
     /// Input is empty.
     Empty,
 }
@@ -80,6 +262,10 @@ impl fmt::Display for ParseAcceleratorError {
             ),
             Self::UnknownModifier(m) => write!(f, "unknown modifier {m:?}"),
             Self::UnknownKey(k) => write!(f, "unknown key {k:?}"),
+            Self::NoPhysicalKey(k) => write!(
+                f,
+                "{k:?} is not on a US QWERTY keyboard, so it can't be matched by position"
+            ),
             Self::Empty => write!(f, "no accelerator provided"),
         }
     }
@@ -95,10 +281,12 @@ impl FromStr for Hotkey {
     /// Parsing is case insensitive.
     ///
     /// Modifiers are one of "ctrl", "control", "alt", "shift" or "meta".
+    /// "physical" can also be given with the modifiers, to match the key
+    /// by its position (see [`KeyMode`]).
     ///
     /// Keys are the character produced when the key is pressed, or for
-    /// enter and tab, the strings "enter" and "tab". See [`Key`] for the
-    /// supported keys.
+    /// keys that don't produce one, their name, like "enter", "escape",
+    /// "up" or "num0". See [`KeyCode`] for the supported keys.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         use ParseAcceleratorError as E;
 
@@ -113,6 +301,7 @@ impl FromStr for Hotkey {
         let mut alt = None;
         let mut shift = None;
         let mut meta = None;
+        let mut physical = None;
 
         for modifier in modifiers {
             match &*modifier.to_lowercase() {
@@ -148,22 +337,43 @@ impl FromStr for Hotkey {
                         ));
                     }
                 }
+                "physical" => {
+                    if let Some(prev) = physical.replace(modifier) {
+                        return Err(E::IncompatibleModifier(
+                            prev.to_string(),
+                            modifier.to_string(),
+                        ));
+                    }
+                }
                 _ => return Err(E::UnknownModifier(modifier.to_string())),
             };
         }
 
+        let mode = if physical.is_some() {
+            if let KeyCode::Char(c) = key {
+                return Err(E::NoPhysicalKey(c.to_string()));
+            }
+            KeyMode::Physical
+        } else {
+            KeyMode::Logical
+        };
+
         Ok(Self {
             key,
             ctrl: ctrl.is_some(),
             alt: alt.is_some(),
             shift: shift.is_some(),
             meta: meta.is_some(),
+            mode,
         })
     }
 }
 
 impl fmt::Display for Hotkey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.mode == KeyMode::Physical {
+            write!(f, "Physical+")?;
+        }
         if self.ctrl {
             write!(f, "Ctrl+")?;
         }
@@ -180,6 +390,208 @@ impl fmt::Display for Hotkey {
     }
 }
 
+/// One or more hotkeys pressed one after another, like `ctrl+k ctrl+d`.
+///
+/// Most sequences are a single hotkey. This deserializes from a string,
+/// a single [`Hotkey`] or a list of hotkeys, and always serializes to a
+/// list.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+pub struct HotkeySequence(Vec<Hotkey>);
+
+impl HotkeySequence {
+    /// Returns [`None`] if `hotkeys` is empty.
+    pub fn new(hotkeys: Vec<Hotkey>) -> Option<Self> {
+        (!hotkeys.is_empty()).then_some(Self(hotkeys))
+    }
+
+    /// The hotkeys in the order they are pressed. This is never empty.
+    pub fn hotkeys(&self) -> &[Hotkey] {
+        &self.0
+    }
+
+    /// Whether the first hotkey is [reserved](Hotkey::is_reserved), so
+    /// the sequence can never be started.
+    pub fn is_reserved(&self) -> bool {
+        self.0[0].is_reserved()
+    }
+}
+
+impl From<Hotkey> for HotkeySequence {
+    fn from(hotkey: Hotkey) -> Self {
+        Self(vec![hotkey])
+    }
+}
+
+impl<'de> Deserialize<'de> for HotkeySequence {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            String(String),
+            Single(Hotkey),
+            Sequence(Vec<Hotkey>),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::String(s) => s.parse().map_err(D::Error::custom),
+            Repr::Single(hotkey) => Ok(Self::from(hotkey)),
+            Repr::Sequence(hotkeys) => Self::new(hotkeys)
+                .ok_or_else(|| D::Error::custom("hotkey sequence must not be empty")),
+        }
+    }
+}
+
+impl FromStr for HotkeySequence {
+    type Err = ParseAcceleratorError;
+
+    /// Hotkeys separated by whitespace, each parsed like [`Hotkey`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hotkeys = s
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<Vec<Hotkey>, _>>()?;
+        Self::new(hotkeys).ok_or(ParseAcceleratorError::Empty)
+    }
+}
+
+impl fmt::Display for HotkeySequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (first, rest) = self.0.split_first().expect("sequence is never empty");
+        write!(f, "{first}")?;
+        for hotkey in rest {
+            write!(f, " {hotkey}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Matches hotkeys as they are pressed against a set of
+/// [`HotkeySequence`]s.
+///
+/// Hotkeys pressed more than the timeout apart are not part of the same
+/// sequence.
+#[derive(Debug, Clone)]
+pub struct SequenceMatcher {
+    timeout: Duration,
+    pressed: Vec<KeyPress>,
+    last_press: Option<Instant>,
+}
+
+/// Result of pressing a hotkey with a [`SequenceMatcher`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceMatch<T> {
+    /// The hotkeys pressed so far are a whole sequence.
+    Matched(T),
+    /// The hotkeys pressed so far start at least one sequence. The rest
+    /// of it needs to be pressed before the timeout.
+    Pending,
+    /// No sequence starts with the hotkeys pressed so far, so they are
+    /// forgotten.
+    NoMatch,
+}
+
+impl<T> SequenceMatch<T> {
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> SequenceMatch<U> {
+        match self {
+            Self::Matched(t) => SequenceMatch::Matched(f(t)),
+            Self::Pending => SequenceMatch::Pending,
+            Self::NoMatch => SequenceMatch::NoMatch,
+        }
+    }
+}
+
+impl SequenceMatcher {
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            pressed: Vec::new(),
+            last_press: None,
+        }
+    }
+
+    /// Keys of an unfinished sequence that have been pressed so far, at
+    /// time `now`.
+    ///
+    /// This is empty once the timeout has passed since the last press.
+    pub fn pending(&self, now: Instant) -> &[KeyPress] {
+        if self.is_expired(now) {
+            &[]
+        } else {
+            &self.pressed
+        }
+    }
+
+    fn is_expired(&self, now: Instant) -> bool {
+        self.last_press
+            .is_some_and(|last| now.saturating_duration_since(last) > self.timeout)
+    }
+
+    /// Forgets the hotkeys pressed so far.
+    pub fn reset(&mut self) {
+        self.pressed.clear();
+        self.last_press = None;
+    }
+
+    /// Presses `press` at time `now`, matching it against the sequence
+    /// of each candidate with [`Hotkey::matches`].
+    ///
+    /// If a sequence is matched exactly, it is returned straight away,
+    /// even if longer sequences start with it. If the pending keys
+    /// followed by `press` don't start any sequence, `press` is tried
+    /// on its own.
+    pub fn press<'a, T>(
+        &mut self,
+        press: KeyPress,
+        now: Instant,
+        candidates: &'a [T],
+        sequence: impl Fn(&T) -> Option<&HotkeySequence>,
+    ) -> SequenceMatch<&'a T> {
+        if self.is_expired(now) {
+            self.pressed.clear();
+        }
+        self.last_press = Some(now);
+        self.pressed.push(press);
+
+        loop {
+            let mut pending = false;
+            for candidate in candidates {
+                let Some(hotkeys) = sequence(candidate).map(HotkeySequence::hotkeys) else {
+                    continue;
+                };
+                let starts_with_pressed = hotkeys.len() >= self.pressed.len()
+                    && hotkeys
+                        .iter()
+                        .zip(&self.pressed)
+                        .all(|(hotkey, press)| hotkey.matches(press));
+                if starts_with_pressed && hotkeys.len() == self.pressed.len() {
+                    self.reset();
+                    return SequenceMatch::Matched(candidate);
+                }
+                pending |= starts_with_pressed;
+            }
+
+            if pending {
+                return SequenceMatch::Pending;
+            }
+            if self.pressed.len() == 1 {
+                self.reset();
+                return SequenceMatch::NoMatch;
+            }
+            // the last key may start a different sequence
+            self.pressed.drain(..self.pressed.len() - 1);
+        }
+    }
+}
+
+impl Default for SequenceMatcher {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_TIMEOUT)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ParseKeyError(String);
 
@@ -194,20 +606,24 @@ impl std::error::Error for ParseKeyError {}
 impl FromStr for KeyCode {
     type Err = ParseKeyError;
 
+    /// Parses the character the key types, or the name of the key.
+    ///
+    /// Parsing is case insensitive. The serialized names, like `digit1`
+    /// or `arrow-up`, are also accepted.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         #[rustfmt::skip]
         let v = match &*s.to_lowercase() {
             // Digits
-            "0" => Self::Digit0,
-            "1" => Self::Digit1,
-            "2" => Self::Digit2,
-            "3" => Self::Digit3,
-            "4" => Self::Digit4,
-            "5" => Self::Digit5,
-            "6" => Self::Digit6,
-            "7" => Self::Digit7,
-            "8" => Self::Digit8,
-            "9" => Self::Digit9,
+            "0" | "digit0" => Self::Digit0,
+            "1" | "digit1" => Self::Digit1,
+            "2" | "digit2" => Self::Digit2,
+            "3" | "digit3" => Self::Digit3,
+            "4" | "digit4" => Self::Digit4,
+            "5" | "digit5" => Self::Digit5,
+            "6" | "digit6" => Self::Digit6,
+            "7" | "digit7" => Self::Digit7,
+            "8" | "digit8" => Self::Digit8,
+            "9" | "digit9" => Self::Digit9,
 
             // Letters
             "a" => Self::A, "b" => Self::B, "c" => Self::C,
@@ -231,31 +647,91 @@ impl FromStr for KeyCode {
             "f22" => Self::F22, "f23" => Self::F23, "f24" => Self::F24,
 
             // Special characters
-            "`" => Self::Backtick,
-            "-" => Self::Hyphen,
-            "=" => Self::Equal,
+            "`" | "backtick" => Self::Backtick,
+            "-" | "hyphen" => Self::Hyphen,
+            "=" | "equal" => Self::Equal,
             "tab" => Self::Tab,
-            "[" => Self::LeftBracket,
-            "]" => Self::RightBracket,
-            "\\" => Self::Backslash,
-            ";" => Self::Semicolon,
-            "'" => Self::Apostrophe,
+            "[" | "left-bracket" => Self::LeftBracket,
+            "]" | "right-bracket" => Self::RightBracket,
+            "\\" | "backslash" => Self::Backslash,
+            ";" | "semicolon" => Self::Semicolon,
+            "'" | "apostrophe" => Self::Apostrophe,
             "enter" => Self::Enter,
-            "," => Self::Comma,
-            "." => Self::Period,
-            "/" => Self::Slash,
+            "," | "comma" => Self::Comma,
+            "." | "period" => Self::Period,
+            "/" | "slash" => Self::Slash,
+
+            // Navigation and editing keys
+            "escape" | "esc" => Self::Escape,
+            "space" => Self::Space,
+            "backspace" => Self::Backspace,
+            "delete" | "del" => Self::Delete,
+            "insert" | "ins" => Self::Insert,
+            "up" | "arrowup" | "arrow-up" => Self::ArrowUp,
+            "down" | "arrowdown" | "arrow-down" => Self::ArrowDown,
+            "left" | "arrowleft" | "arrow-left" => Self::ArrowLeft,
+            "right" | "arrowright" | "arrow-right" => Self::ArrowRight,
+            "home" => Self::Home,
+            "end" => Self::End,
+            "pageup" | "pgup" | "page-up" => Self::PageUp,
+            "pagedown" | "pgdn" | "page-down" => Self::PageDown,
+
+            // Numpad
+            "num0" | "numpad0" => Self::Numpad0,
+            "num1" | "numpad1" => Self::Numpad1,
+            "num2" | "numpad2" => Self::Numpad2,
+            "num3" | "numpad3" => Self::Numpad3,
+            "num4" | "numpad4" => Self::Numpad4,
+            "num5" | "numpad5" => Self::Numpad5,
+            "num6" | "numpad6" => Self::Numpad6,
+            "num7" | "numpad7" => Self::Numpad7,
+            "num8" | "numpad8" => Self::Numpad8,
+            "num9" | "numpad9" => Self::Numpad9,
+            "numadd" | "numpadadd" | "numpad-add" => Self::NumpadAdd,
+            "numsubtract" | "numpadsubtract" | "numpad-subtract" => Self::NumpadSubtract,
+            "nummultiply" | "numpadmultiply" | "numpad-multiply" => Self::NumpadMultiply,
+            "numdivide" | "numpaddivide" | "numpad-divide" => Self::NumpadDivide,
+            "numdecimal" | "numpaddecimal" | "numpad-decimal" => Self::NumpadDecimal,
+            "numenter" | "numpadenter" | "numpad-enter" => Self::NumpadEnter,
 
-            _ => return Err(ParseKeyError(s.to_string())),
+            // keys on other layouts
+            other => {
+                let mut chars = other.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if !c.is_ascii() && !c.is_whitespace() && !c.is_control() => {
+                        Self::Char(c)
+                    }
+                    _ => return Err(ParseKeyError(s.to_string())),
+                }
+            }
         };
 
         Ok(v)
     }
 }
 
+impl<'de> Deserialize<'de> for KeyCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
 impl fmt::Display for KeyCode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         #[rustfmt::skip]
         let s = match self {
+            // like letters, these are shown in uppercase, unless that
+            // wouldn't parse back to the same key, like "SS" for "ß"
+            KeyCode::Char(c) => {
+                let mut upper = c.to_uppercase();
+                return match (upper.next(), upper.next()) {
+                    (Some(u), None) if u.to_lowercase().eq([*c]) => write!(f, "{u}"),
+                    _ => write!(f, "{c}"),
+                };
+            }
+
             // Digits
             KeyCode::Digit0 => "0",
             KeyCode::Digit1 => "1",
@@ -303,8 +779,315 @@ impl fmt::Display for KeyCode {
             KeyCode::Comma => ",",
             KeyCode::Period => ".",
             KeyCode::Slash => "/",
+
+            // Navigation and editing keys
+            KeyCode::Escape => "Escape",
+            KeyCode::Space => "Space",
+            KeyCode::Backspace => "Backspace",
+            KeyCode::Delete => "Delete",
+            KeyCode::Insert => "Insert",
+            KeyCode::ArrowUp => "Up",
+            KeyCode::ArrowDown => "Down",
+            KeyCode::ArrowLeft => "Left",
+            KeyCode::ArrowRight => "Right",
+            KeyCode::Home => "Home",
+            KeyCode::End => "End",
+            KeyCode::PageUp => "PageUp",
+            KeyCode::PageDown => "PageDown",
+
+            // Numpad
+            KeyCode::Numpad0 => "Num0",
+            KeyCode::Numpad1 => "Num1",
+            KeyCode::Numpad2 => "Num2",
+            KeyCode::Numpad3 => "Num3",
+            KeyCode::Numpad4 => "Num4",
+            KeyCode::Numpad5 => "Num5",
+            KeyCode::Numpad6 => "Num6",
+            KeyCode::Numpad7 => "Num7",
+            KeyCode::Numpad8 => "Num8",
+            KeyCode::Numpad9 => "Num9",
+            KeyCode::NumpadAdd => "NumAdd",
+            KeyCode::NumpadSubtract => "NumSubtract",
+            KeyCode::NumpadMultiply => "NumMultiply",
+            KeyCode::NumpadDivide => "NumDivide",
+            KeyCode::NumpadDecimal => "NumDecimal",
+            KeyCode::NumpadEnter => "NumEnter",
         };
 
         f.write_str(s)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{
+        Hotkey, HotkeySequence, KeyCode, KeyMode, KeyPress, SequenceMatch, SequenceMatcher,
+    };
+
+    #[test]
+    fn navigation_keys_round_trip() {
+        for s in [
+            "Ctrl+Backspace",
+            "Alt+Up",
+            "Shift+PageDown",
+            "Meta+Escape",
+            "Ctrl+Num7",
+            "Ctrl+Shift+NumEnter",
+            "Alt+Space",
+        ] {
+            let hotkey: Hotkey = s.parse().unwrap();
+            assert_eq!(hotkey.to_string(), s);
+        }
+    }
+
+    #[test]
+    fn navigation_key_aliases() {
+        assert_eq!("esc".parse(), Ok(KeyCode::Escape));
+        assert_eq!("ArrowLeft".parse(), Ok(KeyCode::ArrowLeft));
+        assert_eq!("pgup".parse(), Ok(KeyCode::PageUp));
+        assert_eq!("numpad3".parse(), Ok(KeyCode::Numpad3));
+    }
+
+    #[test]
+    fn reserved_hotkeys() {
+        let reserved = |s: &str| s.parse::<Hotkey>().unwrap().is_reserved();
+
+        assert!(reserved("escape"));
+        assert!(reserved("down"));
+        assert!(reserved("shift+home"));
+        assert!(reserved("backspace"));
+        assert!(!reserved("ctrl+backspace"));
+        assert!(!reserved("alt+up"));
+        assert!(!reserved("enter"));
+        assert!(!reserved("pagedown"));
+    }
+
+    fn key(s: &str) -> KeyPress {
+        KeyPress::from(s.parse::<Hotkey>().unwrap())
+    }
+
+    #[expect(
+        clippy::unnecessary_wraps,
+        reason = "candidates of `SequenceMatcher::press` may have no sequence"
+    )]
+    fn itself(sequence: &HotkeySequence) -> Option<&HotkeySequence> {
+        Some(sequence)
+    }
+
+    fn sequence(s: &str) -> HotkeySequence {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn sequences_parse_and_round_trip() {
+        let seq = sequence("ctrl+k  ctrl+d");
+        assert_eq!(
+            seq.hotkeys(),
+            [
+                "ctrl+k".parse::<Hotkey>().unwrap(),
+                "ctrl+d".parse().unwrap()
+            ]
+        );
+        assert_eq!(seq.to_string(), "Ctrl+K Ctrl+D");
+        assert_eq!(seq.to_string().parse(), Ok(seq));
+        assert!("".parse::<HotkeySequence>().is_err());
+        assert!("ctrl+k nope".parse::<HotkeySequence>().is_err());
+    }
+
+    #[test]
+    fn sequences_deserialize_from_every_form() {
+        #[derive(serde::Deserialize)]
+        struct Config {
+            string: HotkeySequence,
+            single: HotkeySequence,
+            list: HotkeySequence,
+        }
+
+        let config: Config = toml::from_str(
+            r#"
+            string = "ctrl+k ctrl+d"
+            single = { key = "r", ctrl = true }
+            list = [{ key = "k", ctrl = true }, { key = "d", ctrl = true }]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.string, sequence("ctrl+k ctrl+d"));
+        assert_eq!(config.single, sequence("ctrl+r"));
+        assert_eq!(config.list, sequence("ctrl+k ctrl+d"));
+
+        assert!(toml::from_str::<Config>("string = []\nsingle = 'a'\nlist = 'a'").is_err());
+    }
+
+    #[test]
+    fn matcher_reports_pending_prefix() {
+        let candidates = [
+            sequence("ctrl+k ctrl+d"),
+            sequence("ctrl+k ctrl+u"),
+            sequence("enter"),
+        ];
+        let mut matcher = SequenceMatcher::default();
+        let now = Instant::now();
+        let mut press = |s: &str| matcher.press(key(s), now, &candidates, itself);
+
+        assert_eq!(press("ctrl+k"), SequenceMatch::Pending);
+        assert_eq!(press("ctrl+u"), SequenceMatch::Matched(&candidates[1]));
+        assert_eq!(press("enter"), SequenceMatch::Matched(&candidates[2]));
+        assert_eq!(press("ctrl+d"), SequenceMatch::NoMatch);
+    }
+
+    #[test]
+    fn matcher_restarts_with_the_last_hotkey() {
+        let candidates = [sequence("ctrl+k ctrl+d"), sequence("enter")];
+        let mut matcher = SequenceMatcher::default();
+        let now = Instant::now();
+
+        assert_eq!(
+            matcher.press(key("ctrl+k"), now, &candidates, itself),
+            SequenceMatch::Pending
+        );
+        assert_eq!(matcher.pending(now).len(), 1);
+        assert_eq!(
+            matcher.press(key("enter"), now, &candidates, itself),
+            SequenceMatch::Matched(&candidates[1])
+        );
+        assert!(matcher.pending(now).is_empty());
+    }
+
+    #[test]
+    fn matcher_forgets_pending_hotkeys_after_timeout() {
+        let candidates = [sequence("ctrl+k ctrl+d")];
+        let mut matcher = SequenceMatcher::new(Duration::from_millis(500));
+        let start = Instant::now();
+
+        let k = key("ctrl+k");
+        let d = key("ctrl+d");
+        assert_eq!(
+            matcher.press(k.clone(), start, &candidates, itself),
+            SequenceMatch::Pending
+        );
+        let before_timeout = start + Duration::from_millis(400);
+        let after_timeout = start + Duration::from_millis(600);
+        assert_eq!(matcher.pending(before_timeout), std::slice::from_ref(&k));
+        assert!(matcher.pending(after_timeout).is_empty());
+        assert_eq!(
+            matcher.press(
+                d.clone(),
+                start + Duration::from_secs(1),
+                &candidates,
+                itself
+            ),
+            SequenceMatch::NoMatch
+        );
+
+        let later = start + Duration::from_secs(2);
+        assert_eq!(
+            matcher.press(k, later, &candidates, itself),
+            SequenceMatch::Pending
+        );
+        assert_eq!(
+            matcher.press(d, later + Duration::from_millis(400), &candidates, itself),
+            SequenceMatch::Matched(&candidates[0])
+        );
+    }
+
+    #[test]
+    fn physical_mode_parses_and_round_trips() {
+        let hotkey: Hotkey = "ctrl+physical+z".parse().unwrap();
+        assert_eq!(hotkey.mode, KeyMode::Physical);
+        assert_eq!(hotkey.key, KeyCode::Z);
+        assert_eq!(hotkey.to_string(), "Physical+Ctrl+Z");
+        assert_eq!(hotkey.to_string().parse(), Ok(hotkey));
+
+        let hotkey: Hotkey = "ctrl+z".parse().unwrap();
+        assert_eq!(hotkey.mode, KeyMode::Logical);
+        assert!("physical+physical+z".parse::<Hotkey>().is_err());
+    }
+
+    #[test]
+    fn non_ascii_keys_parse_and_display() {
+        assert_eq!("é".parse(), Ok(KeyCode::Char('é')));
+        assert_eq!("Ж".parse(), Ok(KeyCode::Char('ж')));
+        assert_eq!(KeyCode::Char('ß').to_string(), "ß");
+        assert!("!".parse::<KeyCode>().is_err());
+        assert!("ab".parse::<KeyCode>().is_err());
+
+        let hotkey: Hotkey = "Alt+É".parse().unwrap();
+        assert_eq!(hotkey.key, KeyCode::Char('é'));
+        assert_eq!(hotkey.to_string(), "Alt+É");
+        assert!("physical+é".parse::<Hotkey>().is_err());
+
+        // dotless i and long s uppercase to the ASCII letters I and S
+        for c in ['é', 'ж', 'ß', 'ı', 'ſ', 'ς'] {
+            let key = KeyCode::Char(c);
+            assert_eq!(key.to_string().parse(), Ok(key));
+        }
+    }
+
+    #[test]
+    fn keys_deserialize_like_they_parse() {
+        let key = |s: &str| serde_json::from_value::<KeyCode>(serde_json::json!(s));
+        assert_eq!(key("A").unwrap(), KeyCode::A);
+        assert_eq!(key("Ж").unwrap(), KeyCode::Char('ж'));
+        assert_eq!(key("arrow-up").unwrap(), KeyCode::ArrowUp);
+        assert_eq!(key("up").unwrap(), KeyCode::ArrowUp);
+        assert!(key("!").is_err());
+
+        for key in [
+            KeyCode::Digit1,
+            KeyCode::LeftBracket,
+            KeyCode::PageDown,
+            KeyCode::NumpadAdd,
+            KeyCode::Char('é'),
+        ] {
+            let value = serde_json::to_value(&key).unwrap();
+            assert_eq!(serde_json::from_value::<KeyCode>(value).unwrap(), key);
+        }
+    }
+
+    #[test]
+    fn physical_hotkeys_cannot_deserialize_other_characters() {
+        let hotkey = |s: &str| toml::from_str::<Hotkey>(s);
+        assert!(hotkey("key = \"é\"\nmode = \"physical\"").is_err());
+        assert_eq!(
+            hotkey("key = \"q\"\nmode = \"physical\"").unwrap(),
+            "physical+q".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn non_ascii_keys_serialize_as_strings() {
+        let hotkey: Hotkey = toml::from_str("key = \"ж\"\nctrl = true").unwrap();
+        assert_eq!(hotkey.key, KeyCode::Char('ж'));
+        assert_eq!(hotkey.mode, KeyMode::Logical);
+        assert_eq!(
+            serde_json::to_value(&hotkey.key).unwrap(),
+            serde_json::json!("ж")
+        );
+        assert_eq!(
+            serde_json::to_value(KeyCode::ArrowUp).unwrap(),
+            serde_json::json!("arrow-up")
+        );
+    }
+
+    #[test]
+    fn hotkeys_match_by_mode() {
+        // the key left of W on a QWERTY keyboard, on an AZERTY keyboard
+        let press = KeyPress {
+            physical: Some(KeyCode::Q),
+            logical: Some(KeyCode::A),
+            ctrl: true,
+            alt: false,
+            shift: false,
+            meta: false,
+        };
+
+        let matches = |s: &str| s.parse::<Hotkey>().unwrap().matches(&press);
+        assert!(matches("ctrl+a"));
+        assert!(!matches("ctrl+q"));
+        assert!(matches("ctrl+physical+q"));
+        assert!(!matches("ctrl+physical+a"));
+        assert!(!matches("a"), "modifiers must be the same");
+    }
+}
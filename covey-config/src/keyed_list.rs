@@ -73,6 +73,10 @@ impl<T: Identify> KeyedList<T> {
     pub fn iter(&self) -> slice::Iter<'_, T> {
         self.items.iter()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
 }
 
 impl<T> Default for KeyedList<T> {
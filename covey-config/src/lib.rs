@@ -15,6 +15,7 @@
 //! [`Deserialize`]: serde::Deserialize
 
 pub mod config;
+pub mod conflicts;
 #[doc(hidden)]
 pub mod generate;
 pub mod hotkey;
@@ -1,6 +1,7 @@
 //! Types for the plugin manifest.
 
 use std::{
+    cmp,
     collections::HashMap,
     fmt::{self, Debug},
     marker::PhantomData,
@@ -12,7 +13,7 @@ use serde::{
 };
 
 use crate::{
-    hotkey::Hotkey,
+    hotkey::HotkeySequence,
     keyed_list::{Id, Identify, KeyedList},
 };
 
@@ -53,14 +54,48 @@ impl PluginManifest {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+/// Commands are compared and ordered by their [`id`](Command::id), which is
+/// unique within a plugin.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[serde(rename_all = "kebab-case")]
 pub struct Command {
+    /// Can't start with `#`, which is used for commands that plugins add
+    /// to list items at runtime.
+    #[serde(deserialize_with = "command_id")]
     pub id: Id,
     pub title: String,
     pub description: Option<String>,
-    pub default_hotkey: Option<Hotkey>,
+    /// Hotkey that activates this command, which can be a sequence like
+    /// `"ctrl+k ctrl+d"`. Users can change this in their config.
+    pub default_hotkey: Option<HotkeySequence>,
+    /// Whether the user must confirm before this command is activated.
+    ///
+    /// This should be set on destructive commands, like deleting a file.
+    #[serde(default)]
+    pub confirm: bool,
+    /// Values that the user is asked for before this command is run.
+    ///
+    /// The values are passed to the command's callback.
+    #[serde(default)]
+    pub parameters: KeyedList<PluginConfigSchema>,
+}
+
+impl Command {
+    /// Checks that the user's values for [`Command::parameters`] are valid.
+    ///
+    /// Parameters with a default value may be omitted.
+    pub fn validate_parameters(
+        &self,
+        values: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<(), ValidationError> {
+        validate_fields(
+            self.parameters
+                .iter()
+                .map(|param| (param.id.as_str(), &param.r#type)),
+            values,
+        )
+    }
 }
 
 impl Identify for Command {
@@ -69,6 +104,26 @@ impl Identify for Command {
     }
 }
 
+impl PartialEq for Command {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Command {}
+
+impl PartialOrd for Command {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Command {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
 fn default_commands() -> KeyedList<Command> {
     KeyedList::new(vec![
         Command {
@@ -76,18 +131,24 @@ fn default_commands() -> KeyedList<Command> {
             title: String::from("Activate"),
             description: None,
             default_hotkey: Some("enter".parse().expect("enter should be a hotkey")),
+            confirm: false,
+            parameters: KeyedList::default(),
         },
         Command {
             id: Id::new("complete"),
             title: String::from("Complete"),
             description: None,
             default_hotkey: Some("tab".parse().expect("tab should be a hotkey")),
+            confirm: false,
+            parameters: KeyedList::default(),
         },
         Command {
             id: Id::new("alt-activate"),
             title: String::from("Alt activate"),
             description: None,
             default_hotkey: Some("alt+enter".parse().expect("alt+enter should be a hotkey")),
+            confirm: false,
+            parameters: KeyedList::default(),
         },
     ])
     .expect("ids are unique")
@@ -127,6 +188,168 @@ pub enum SchemaType {
     Struct(SchemaStruct),
 }
 
+impl SchemaType {
+    /// Whether a value can be omitted, in which case a default is used.
+    pub fn has_default(&self) -> bool {
+        match self {
+            Self::Int(int) => int.default.is_some(),
+            Self::Text(text) => text.default.is_some(),
+            Self::Bool(bool) => bool.default.is_some(),
+            Self::FilePath(file) => file.default.is_some(),
+            Self::FolderPath(folder) => folder.default.is_some(),
+            Self::Selection(selection) => selection.default.is_some(),
+            Self::List(_) | Self::Map(_) => true,
+            Self::Struct(_) => false,
+        }
+    }
+
+    /// Checks that a JSON value matches this schema.
+    ///
+    /// This performs the same checks as the types generated by
+    /// `include_manifest!`.
+    pub fn validate(&self, value: &serde_json::Value) -> Result<(), ValidationError> {
+        use serde_json::Value;
+
+        let invalid = |message: String| Err(ValidationError::new(message));
+
+        match (self, value) {
+            (Self::Int(SchemaInt { min, max, .. }), Value::Number(number)) => {
+                let Some(int) = number.as_i64().and_then(|int| i32::try_from(int).ok()) else {
+                    return invalid(format!("expected an integer, found {number}"));
+                };
+                if int < *min {
+                    return invalid(format!("must be at least {min}"));
+                }
+                if int > *max {
+                    return invalid(format!("must be at most {max}"));
+                }
+            }
+            (
+                Self::Text(SchemaText {
+                    min_length,
+                    max_length,
+                    ..
+                }),
+                Value::String(text),
+            ) => {
+                let len = u32::try_from(text.len()).unwrap_or(u32::MAX);
+                if len < *min_length {
+                    return invalid(format!("length must be at least {min_length}"));
+                }
+                if len > *max_length {
+                    return invalid(format!("length must be at most {max_length}"));
+                }
+            }
+            (Self::Bool(_), Value::Bool(_)) | (Self::FolderPath(_), Value::String(_)) => {}
+            (Self::FilePath(SchemaFilePath { extension, .. }), Value::String(path)) => {
+                let ext = std::path::Path::new(path).extension();
+                if let Some(allowed) = extension
+                    && !allowed.iter().any(|allowed| ext == Some(allowed.as_ref()))
+                {
+                    return invalid(format!("must have one of the extensions {allowed:?}"));
+                }
+            }
+            (Self::Selection(SchemaSelection { allowed_values, .. }), Value::String(selected)) => {
+                if !allowed_values.contains(selected) {
+                    return invalid(format!("must be one of {allowed_values:?}"));
+                }
+            }
+            (
+                Self::List(SchemaList {
+                    item_type,
+                    min_items,
+                    unique,
+                }),
+                Value::Array(items),
+            ) => {
+                for (i, item) in items.iter().enumerate() {
+                    item_type
+                        .validate(item)
+                        .map_err(|e| e.within(&i.to_string()))?;
+                }
+                if u32::try_from(items.len()).unwrap_or(u32::MAX) < *min_items {
+                    return invalid(format!("must have at least {min_items} items"));
+                }
+                if *unique
+                    && items
+                        .iter()
+                        .enumerate()
+                        .any(|(i, item)| items[..i].contains(item))
+                {
+                    return invalid(String::from("must not have duplicate items"));
+                }
+            }
+            (
+                Self::Map(SchemaMap {
+                    value_type,
+                    min_items,
+                }),
+                Value::Object(entries),
+            ) => {
+                for (key, value) in entries {
+                    value_type.validate(value).map_err(|e| e.within(key))?;
+                }
+                if u32::try_from(entries.len()).unwrap_or(u32::MAX) < *min_items {
+                    return invalid(format!("must have at least {min_items} entries"));
+                }
+            }
+            (Self::Struct(SchemaStruct { fields }), Value::Object(entries)) => {
+                validate_fields(fields.iter().map(|(key, ty)| (key.as_str(), ty)), entries)?;
+            }
+            (_, value) => return invalid(format!("unexpected value {value}")),
+        }
+
+        Ok(())
+    }
+}
+
+fn validate_fields<'a>(
+    fields: impl IntoIterator<Item = (&'a str, &'a SchemaType)>,
+    entries: &serde_json::Map<String, serde_json::Value>,
+) -> Result<(), ValidationError> {
+    for (key, ty) in fields {
+        match entries.get(key) {
+            Some(value) => ty.validate(value).map_err(|e| e.within(key))?,
+            None if ty.has_default() => {}
+            None => return Err(ValidationError::new(String::from("is required")).within(key)),
+        }
+    }
+    Ok(())
+}
+
+/// A value that doesn't match a [`SchemaType`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// Keys and indices to the invalid value, outermost first.
+    pub path: Vec<String>,
+    pub message: String,
+}
+
+impl ValidationError {
+    fn new(message: String) -> Self {
+        Self {
+            path: vec![],
+            message,
+        }
+    }
+
+    fn within(mut self, key: &str) -> Self {
+        self.path.insert(0, key.to_owned());
+        self
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.path.is_empty() {
+            write!(f, "{}: ", self.path.join("."))?;
+        }
+        f.write_str(&self.message)
+    }
+}
+
+impl core::error::Error for ValidationError {}
+
 // the below structs can't use the macro because they have extra
 // required fields.
 // all structs should have the same serde meta tag.
@@ -262,6 +485,20 @@ impl<'de> Deserialize<'de> for SchemaType {
     }
 }
 
+fn command_id<'de, D>(deserializer: D) -> Result<Id, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let id = Id::deserialize(deserializer)?;
+    if id.as_str().starts_with('#') {
+        return Err(de::Error::invalid_value(
+            de::Unexpected::Str(id.as_str()),
+            &"a command ID that doesn't start with `#`",
+        ));
+    }
+    Ok(id)
+}
+
 /// [`FromStr`] that is just one of several possibilities.
 ///
 /// The error type should be the possible variants.
@@ -355,7 +592,7 @@ mod tests {
 
     use super::{
         PluginConfigSchema, PluginManifest, SchemaInt, SchemaList, SchemaMap, SchemaStruct,
-        SchemaType,
+        SchemaText, SchemaType,
     };
     use crate::{
         keyed_list::{Id, KeyedList},
@@ -484,4 +721,85 @@ mod tests {
             })
         )
     }
+
+    #[test]
+    fn command_parameters() {
+        let input = r#"
+            name = "Timer"
+
+            [[commands]]
+            id = "start"
+            title = "Start timer"
+
+            [[commands.parameters]]
+            id = "minutes"
+            title = "Minutes"
+            type.int = { min = 1, max = 120 }
+
+            [[commands.parameters]]
+            id = "label"
+            title = "Label"
+            type.text = { default = "timer" }
+        "#;
+        let output: PluginManifest = toml::from_str(input).unwrap();
+        let command = output.commands.get("start").unwrap();
+        assert_eq!(
+            command.parameters.get("minutes").unwrap().r#type,
+            SchemaType::Int(SchemaInt {
+                min: 1,
+                max: 120,
+                default: None
+            })
+        );
+        assert_eq!(
+            command.parameters.get("label").unwrap().r#type,
+            SchemaType::Text(SchemaText {
+                default: Some("timer".to_string()),
+                ..Default::default()
+            })
+        );
+
+        let values = |json: serde_json::Value| json.as_object().unwrap().clone();
+        assert!(
+            command
+                .validate_parameters(&values(serde_json::json!({ "minutes": 5 })))
+                .is_ok()
+        );
+        assert_eq!(
+            command
+                .validate_parameters(&values(serde_json::json!({ "minutes": 0 })))
+                .unwrap_err()
+                .to_string(),
+            "minutes: must be at least 1"
+        );
+        assert_eq!(
+            command
+                .validate_parameters(&values(serde_json::json!({ "label": "tea" })))
+                .unwrap_err()
+                .to_string(),
+            "minutes: is required"
+        );
+    }
+
+    #[test]
+    fn command_ids_cannot_start_with_hash() {
+        let manifest = |id: &str| {
+            PluginManifest::try_from_toml(&format!(
+                r#"
+                name = "test"
+
+                [[commands]]
+                id = "{id}"
+                title = "Command"
+                "#
+            ))
+        };
+
+        assert!(manifest("open-#1").is_ok());
+        let error = manifest("#1").unwrap_err();
+        assert!(
+            error.message().contains("doesn't start with `#`"),
+            "{error}"
+        );
+    }
 }
@@ -1,24 +1,145 @@
-use crate::{proto, Input};
+use crate::{
+    proto,
+    store::{OutputStore, PageStore},
+    Capture, Command, Input, Page,
+};
 
 #[derive(Debug, Clone)]
 pub enum Action {
     Close,
+    /// Run a command in covey's working directory, detached from covey.
     RunCommand(String, Vec<String>),
+    /// Run a command with a working directory and environment, detached
+    /// from covey.
+    Run(Command),
     RunShell(String),
+    /// Run a command, waiting for it to finish.
+    ///
+    /// See [`Capture`] for more details.
+    RunCaptured(Capture),
+    /// Run a command in the user's terminal emulator.
+    ///
+    /// The terminal can be configured by the user.
+    RunInTerminal {
+        /// The terminal runs the command in its working directory, with
+        /// its environment variables.
+        command: Command,
+        /// Keep the terminal open after the command exits, so that the
+        /// user can read the output.
+        hold: bool,
+    },
     Copy(String),
+    /// Close the window and insert text into the previously focused window.
+    ///
+    /// The frontend chooses how the text is inserted. If it can't insert
+    /// text, it will be copied to the clipboard instead.
+    Paste(String),
     SetInput(Input),
+    /// Open a page on top of the current list.
+    ///
+    /// See [`Page`] for more details.
+    PushPage(Page),
+    /// Ask the user to confirm before running some actions.
+    ///
+    /// The actions are only run if the user confirms. Destructive commands
+    /// that always need confirmation should set `confirm = true` in the
+    /// manifest instead.
+    Confirm {
+        /// Message to show the user, e.g. "Delete 3 files?".
+        message: String,
+        actions: Vec<Action>,
+    },
+    /// Show a desktop notification.
+    ///
+    /// If the frontend is not able to show notifications, this action
+    /// is ignored.
+    Notify {
+        title: String,
+        /// Can be empty.
+        body: String,
+        /// A named icon, taken from the system.
+        icon: Option<String>,
+        urgency: Urgency,
+    },
 }
 
 impl Action {
-    pub(crate) fn into_proto(self) -> proto::Action {
+    /// Shows a notification with normal urgency and no icon.
+    pub fn notify(title: impl Into<String>, body: impl Into<String>) -> Self {
+        Self::Notify {
+            title: title.into(),
+            body: body.into(),
+            icon: None,
+            urgency: Urgency::Normal,
+        }
+    }
+
+    /// Runs a command in a terminal, closing the terminal once it exits.
+    pub fn run_in_terminal(command: Command) -> Self {
+        Self::RunInTerminal {
+            command,
+            hold: false,
+        }
+    }
+
+    /// Asks the user to confirm before running the actions.
+    pub fn confirm(message: impl Into<String>, actions: impl Into<Actions>) -> Self {
+        Self::Confirm {
+            message: message.into(),
+            actions: actions.into().list,
+        }
+    }
+
+    /// Converts this into a protobuf action.
+    ///
+    /// Pages and output callbacks need to be stored so that they can be
+    /// called later.
+    pub(crate) fn into_proto(
+        self,
+        pages: &mut PageStore,
+        outputs: &mut OutputStore,
+    ) -> proto::Action {
         use proto::action::Action as PrAction;
 
         let inner_action = match self {
             Self::Close => PrAction::Close(()),
-            Self::RunCommand(cmd, args) => PrAction::RunCommand(proto::Command { cmd, args }),
+            Self::RunCommand(cmd, args) => {
+                PrAction::RunCommand(Command::new(cmd, args).into_proto())
+            }
+            Self::Run(command) => PrAction::RunCommand(command.into_proto()),
             Self::RunShell(str) => PrAction::RunShell(str),
+            Self::RunCaptured(capture) => PrAction::RunCaptured(capture.into_proto(outputs)),
+            Self::RunInTerminal { command, hold } => {
+                PrAction::RunInTerminal(proto::RunInTerminal {
+                    command: command.into_proto(),
+                    hold: Some(hold),
+                })
+            }
             Self::Copy(str) => PrAction::Copy(str),
+            Self::Paste(str) => PrAction::Paste(str),
             Self::SetInput(input) => PrAction::SetInput(input.into_proto()),
+            Self::PushPage(page) => {
+                let id = pages.store(page.query.clone());
+                PrAction::PushPage(page.into_proto(id))
+            }
+            Self::Confirm { message, actions } => PrAction::Confirm(proto::Confirm {
+                message,
+                actions: actions
+                    .into_iter()
+                    .map(|action| action.into_proto(pages, outputs))
+                    .collect(),
+            }),
+            Self::Notify {
+                title,
+                body,
+                icon,
+                urgency,
+            } => PrAction::Notify(proto::Notification {
+                title,
+                body,
+                icon,
+                urgency: Some(urgency.into_proto().into()),
+            }),
         };
 
         proto::Action {
@@ -27,12 +148,35 @@ impl Action {
     }
 }
 
+/// How important a notification is.
+///
+/// This is a hint to the frontend: some frontends may treat all
+/// urgencies the same.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+impl Urgency {
+    pub(crate) fn into_proto(self) -> proto::Urgency {
+        match self {
+            Self::Low => proto::Urgency::Low,
+            Self::Normal => proto::Urgency::Normal,
+            Self::Critical => proto::Urgency::Critical,
+        }
+    }
+}
+
 /// Wrapper for a [`Vec<Action>`] with convenient conversion trait implementations.
 ///
 /// [`From`] Implementations:
 /// - [`IntoIterator<Item = Action>`] -> `Vec<Action>`
 /// - [`Action`] -> `vec![Action]`
 /// - [`Input`] -> `vec![Action::SetInput(Input)]`
+/// - [`Page`] -> `vec![Action::PushPage(Page)]`
 pub struct Actions {
     pub(crate) list: Vec<Action>,
 }
@@ -56,3 +200,9 @@ impl From<Input> for Actions {
         Self::from(Action::SetInput(value))
     }
 }
+
+impl From<Page> for Actions {
+    fn from(value: Page) -> Self {
+        Self::from(Action::PushPage(value))
+    }
+}
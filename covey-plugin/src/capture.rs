@@ -0,0 +1,138 @@
+use std::{fmt, future::Future, sync::Arc};
+
+use anyhow::Result;
+
+use crate::{
+    list::DynFuture, proto, store::OutputStore, Action, Actions, Command, DynamicCommand, Hotkey,
+    KeyCode, KeyMode, List, ListItem, Page,
+};
+
+pub(crate) type OutputFunction = Arc<dyn Fn(Output) -> DynFuture<Result<Actions>> + Send + Sync>;
+
+/// A command whose exit status and output are captured.
+///
+/// Run this with [`Action::RunCaptured`]. Unlike [`Action::RunCommand`],
+/// covey waits for the command to finish. If it fails, stderr is shown to
+/// the user as an error.
+///
+/// To do something with the output, use [`Capture::on_output`] or
+/// [`Capture::show_output`].
+#[derive(Clone)]
+pub struct Capture {
+    program: Program,
+    pub(crate) on_output: Option<OutputFunction>,
+}
+
+#[derive(Debug, Clone)]
+enum Program {
+    Command(String, Vec<String>),
+    Shell(String),
+}
+
+/// Output of a command that succeeded.
+#[derive(Debug, Clone)]
+pub struct Output {
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl Capture {
+    pub fn command(cmd: impl Into<String>, args: impl IntoIterator<Item: Into<String>>) -> Self {
+        Self {
+            program: Program::Command(cmd.into(), args.into_iter().map(Into::into).collect()),
+            on_output: None,
+        }
+    }
+
+    /// Runs a command with `sh -c`.
+    pub fn shell(str: impl Into<String>) -> Self {
+        Self {
+            program: Program::Shell(str.into()),
+            on_output: None,
+        }
+    }
+
+    /// Calls `callback` with the output once the command succeeds.
+    ///
+    /// The callback is not called if the command fails.
+    #[must_use = "builder method consumes self"]
+    pub fn on_output<Fut>(
+        mut self,
+        callback: impl Fn(Output) -> Fut + Send + Sync + 'static,
+    ) -> Self
+    where
+        Fut: Future<Output = Result<Actions>> + Send + Sync + 'static,
+    {
+        self.on_output = Some(Arc::new(move |output| Box::pin(callback(output))));
+        self
+    }
+
+    /// Opens a [`Page`] listing every line of stdout once the command
+    /// succeeds.
+    ///
+    /// The query filters the lines, and activating a line copies it.
+    #[must_use = "builder method consumes self"]
+    pub fn show_output(self) -> Self {
+        self.on_output(|output| async move {
+            let lines: Arc<[String]> = output.stdout.lines().map(ToOwned::to_owned).collect();
+
+            Ok(Actions::from(Page::new(move |query| {
+                let lines = Arc::clone(&lines);
+                async move {
+                    Ok(List::new(
+                        lines
+                            .iter()
+                            .filter(|line| line.contains(&query))
+                            .map(|line| copy_line_item(line.clone()))
+                            .collect(),
+                    ))
+                }
+            })))
+        })
+    }
+
+    pub(crate) fn into_proto(self, outputs: &mut OutputStore) -> proto::RunCaptured {
+        use proto::run_captured::Program as PrProgram;
+
+        proto::RunCaptured {
+            program: Some(match self.program {
+                Program::Command(cmd, args) => {
+                    PrProgram::Command(Command::new(cmd, args).into_proto())
+                }
+                Program::Shell(str) => PrProgram::Shell(str),
+            }),
+            callback_id: self.on_output.map(|callback| outputs.store(callback)),
+        }
+    }
+}
+
+/// A list item that copies `line` when enter is pressed.
+///
+/// This uses a dynamic command, so that it works whatever commands are in
+/// the plugin's manifest.
+fn copy_line_item(line: String) -> ListItem {
+    let enter = Hotkey {
+        key: KeyCode::Enter,
+        ctrl: false,
+        alt: false,
+        shift: false,
+        meta: false,
+        mode: KeyMode::Logical,
+    };
+    ListItem::new(line.clone()).add_dynamic_command(
+        DynamicCommand::new("Copy", move || {
+            let line = line.clone();
+            async move { Ok(Actions::from([Action::Close, Action::Copy(line)])) }
+        })
+        .with_hotkey(enter),
+    )
+}
+
+impl fmt::Debug for Capture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Capture")
+            .field("program", &self.program)
+            .field("on_output", &self.on_output.is_some())
+            .finish()
+    }
+}
@@ -0,0 +1,58 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use crate::proto;
+
+/// A command to run, with its working directory and environment.
+///
+/// Run this with [`Action::Run`](crate::Action::Run) or
+/// [`Action::RunInTerminal`](crate::Action::RunInTerminal).
+#[derive(Debug, Clone)]
+pub struct Command {
+    pub cmd: String,
+    pub args: Vec<String>,
+    /// Working directory of the command.
+    ///
+    /// If this is [`None`], covey's working directory is used.
+    pub cwd: Option<PathBuf>,
+    /// Extra environment variables to set.
+    ///
+    /// Some of covey's own environment variables are removed, such as
+    /// `RUST_LOG`. The user can configure which are removed.
+    pub env: HashMap<String, String>,
+}
+
+impl Command {
+    /// A command with covey's working directory and no extra environment
+    /// variables.
+    pub fn new(cmd: impl Into<String>, args: impl IntoIterator<Item: Into<String>>) -> Self {
+        Self {
+            cmd: cmd.into(),
+            args: args.into_iter().map(Into::into).collect(),
+            cwd: None,
+            env: HashMap::new(),
+        }
+    }
+
+    /// Sets the working directory.
+    #[must_use = "builder method consumes self"]
+    pub fn cwd(mut self, cwd: impl Into<PathBuf>) -> Self {
+        self.cwd = Some(cwd.into());
+        self
+    }
+
+    /// Sets an environment variable.
+    #[must_use = "builder method consumes self"]
+    pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.insert(key.into(), value.into());
+        self
+    }
+
+    pub(crate) fn into_proto(self) -> proto::Command {
+        proto::Command {
+            cmd: self.cmd,
+            args: self.args,
+            cwd: self.cwd.map(|cwd| cwd.to_string_lossy().into_owned()),
+            env: self.env,
+        }
+    }
+}
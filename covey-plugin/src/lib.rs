@@ -4,13 +4,21 @@ pub mod rank;
 mod list;
 use std::{path::PathBuf, sync::OnceLock};
 
-pub use list::{Icon, List, ListItem, ListStyle};
+pub use list::{Accessory, DynamicCommand, Icon, List, ListItem, ListStyle, TagColor};
 mod action;
-pub use action::{Action, Actions};
+pub use action::{Action, Actions, Urgency};
+mod capture;
+pub use capture::{Capture, Output};
+mod command;
+pub use command::Command;
 mod input;
 pub use input::{Input, SelectionRange};
+mod page;
+pub use page::Page;
 mod plugin;
 pub use plugin::Plugin;
+mod preview;
+pub use preview::Preview;
 mod server;
 pub use server::run_server as main;
 mod plugin_lock;
@@ -22,6 +30,7 @@ mod proto {
 }
 
 pub use anyhow::{self, Result};
+pub use covey_config::hotkey::{Hotkey, HotkeySequence, KeyCode, KeyMode};
 
 /// ID of this plugin.
 ///
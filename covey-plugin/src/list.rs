@@ -1,22 +1,58 @@
-use std::{collections::HashMap, future::Future, pin::Pin, sync::Arc};
+use std::{collections::HashMap, future::Future, ops::Range, path::PathBuf, pin::Pin, sync::Arc};
 
-use anyhow::Result;
+use anyhow::{Context as _, Result};
+use covey_config::hotkey::HotkeySequence;
+use serde::de::DeserializeOwned;
 
-use crate::{proto, Action, Actions};
+use crate::{preview::PreviewFunction, proto, Action, Actions, Preview};
 
 pub struct List {
     pub(crate) items: Vec<ListItem>,
+    /// Section titles and the index of the first item in them.
+    pub(crate) sections: Vec<(String, usize)>,
     /// The kind of list to show.
     ///
     /// If this is [`None`], the list style will be the default set by
     /// the user. Plugins should only set one if the content makes the most
     /// sense with one of these styles.
     pub(crate) style: Option<ListStyle>,
+    /// Whether the query should be left out of the user's history.
+    pub(crate) private: bool,
 }
 
 impl List {
     pub fn new(items: Vec<ListItem>) -> Self {
-        Self { items, style: None }
+        Self {
+            items,
+            sections: vec![],
+            style: None,
+            private: false,
+        }
+    }
+
+    /// Creates a list with the items grouped under section headers.
+    ///
+    /// Sections are shown in order. Empty sections are not shown.
+    pub fn sectioned<S: Into<String>>(
+        sections: impl IntoIterator<Item = (S, Vec<ListItem>)>,
+    ) -> Self {
+        let mut list = Self::new(vec![]);
+        for (title, items) in sections {
+            list = list.with_section(title, items);
+        }
+        list
+    }
+
+    /// Adds a section with a header to the end of the list.
+    ///
+    /// Empty sections are not shown.
+    #[must_use = "builder method consumes self"]
+    pub fn with_section(mut self, title: impl Into<String>, items: Vec<ListItem>) -> Self {
+        if !items.is_empty() {
+            self.sections.push((title.into(), self.items.len()));
+            self.items.extend(items);
+        }
+        self
     }
 
     #[must_use = "builder method consumes self"]
@@ -36,6 +72,14 @@ impl List {
         self.style = Some(ListStyle::Rows);
         self
     }
+
+    /// Keeps the query out of the user's history, e.g. because it
+    /// contains a password.
+    #[must_use = "builder method consumes self"]
+    pub fn private(mut self) -> Self {
+        self.private = true;
+        self
+    }
 }
 
 #[non_exhaustive]
@@ -59,24 +103,55 @@ impl ListStyle {
 // This should only be converted into a proto::ListItem via the ListItemStore.
 #[derive(Clone)]
 pub struct ListItem {
+    /// Identifies the item across queries, e.g. a file path or a URL.
+    ///
+    /// This is used to remember which items the user activated, for
+    /// [`rank`]. If this is [`None`], the title is used, so items with the
+    /// same title share their history and renaming an item loses it.
+    ///
+    /// [`rank`]: crate::rank::rank
+    pub key: Option<String>,
     pub title: String,
     pub description: String,
     pub icon: Option<Icon>,
+    /// Ranges of characters in the title to highlight.
+    ///
+    /// Indices count [`char`]s, not bytes. [`rank`] fills these in with
+    /// the characters that matched the query.
+    ///
+    /// [`rank`]: crate::rank::rank
+    pub title_highlights: Vec<Range<usize>>,
+    /// Ranges of characters in the description to highlight.
+    ///
+    /// Same as [`ListItem::title_highlights`].
+    pub description_highlights: Vec<Range<usize>>,
+    /// Small extra information shown next to the item, in order.
+    pub accessories: Vec<Accessory>,
     /// Key is the command's ID.
     pub(crate) commands: ListItemCallbacks,
 }
 
 impl ListItem {
     pub fn new(title: impl Into<String>) -> Self {
-        let title = title.into();
         Self {
-            title: title.clone(),
+            key: None,
+            title: title.into(),
             icon: None,
             description: String::new(),
-            commands: ListItemCallbacks::new(title),
+            title_highlights: vec![],
+            description_highlights: vec![],
+            accessories: vec![],
+            commands: ListItemCallbacks::new(),
         }
     }
 
+    /// Sets the [key](ListItem::key) that identifies this item.
+    #[must_use = "builder method consumes self"]
+    pub fn with_key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
     #[must_use = "builder method consumes self"]
     pub fn with_description(mut self, desc: impl Into<String>) -> Self {
         self.description = desc.into();
@@ -101,66 +176,370 @@ impl ListItem {
         self
     }
 
+    #[must_use = "builder method consumes self"]
+    pub fn with_icon_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.icon = Some(Icon::File(path.into()));
+        self
+    }
+
+    #[must_use = "builder method consumes self"]
+    pub fn with_title_highlights(mut self, ranges: impl IntoIterator<Item = Range<usize>>) -> Self {
+        self.title_highlights = ranges.into_iter().collect();
+        self
+    }
+
+    #[must_use = "builder method consumes self"]
+    pub fn with_description_highlights(
+        mut self,
+        ranges: impl IntoIterator<Item = Range<usize>>,
+    ) -> Self {
+        self.description_highlights = ranges.into_iter().collect();
+        self
+    }
+
+    /// Adds an accessory after any existing ones.
+    #[must_use = "builder method consumes self"]
+    pub fn add_accessory(mut self, accessory: Accessory) -> Self {
+        self.accessories.push(accessory);
+        self
+    }
+
+    /// Adds a command that is not declared in the manifest.
+    ///
+    /// This is useful when the available commands depend on the item or
+    /// on the user's system, such as opening the item in each installed
+    /// browser. Commands that are known ahead of time should be declared
+    /// in the manifest instead, so that users can change their hotkeys.
+    #[must_use = "builder method consumes self"]
+    pub fn add_dynamic_command(mut self, command: DynamicCommand) -> Self {
+        self.commands.dynamic.push(command);
+        self
+    }
+
+    /// Adds a larger preview of this item.
+    ///
+    /// `callback` is only called when the item is selected, so it can do
+    /// more expensive work like reading files.
+    #[must_use = "builder method consumes self"]
+    pub fn on_preview<Fut>(mut self, callback: impl Fn() -> Fut + Send + Sync + 'static) -> Self
+    where
+        Fut: Future<Output = Result<Preview>> + Send + Sync + 'static,
+    {
+        self.commands.preview = Some(Arc::new(move || Box::pin(callback())));
+        self
+    }
+
     /// Adds a command that can be called.
     ///
     /// This should not be used directly, use the extension trait generated
     /// by [`crate::include_manifest!`] instead.
     #[doc(hidden)]
     pub fn add_command(mut self, name: &'static str, callback: ActivationFunction) -> Self {
-        self.commands.add_command(name, callback);
+        self.commands
+            .add_command(name, Arc::new(move |_| callback()));
+        self
+    }
+
+    /// Adds a command that is called with the parameters the user entered.
+    ///
+    /// This should not be used directly, use the extension trait generated
+    /// by [`crate::include_manifest!`] instead.
+    #[doc(hidden)]
+    #[must_use = "builder method consumes self"]
+    pub fn add_command_with_params<P, Fut>(
+        mut self,
+        name: &'static str,
+        callback: impl Fn(P) -> Fut + Send + Sync + 'static,
+    ) -> Self
+    where
+        P: DeserializeOwned + Send + Sync + 'static,
+        Fut: Future<Output = Result<Actions>> + Send + Sync + 'static,
+    {
+        let callback = Arc::new(callback);
+        self.commands.add_command(
+            name,
+            Arc::new(move |params: Option<String>| {
+                let callback = Arc::clone(&callback);
+                Box::pin(async move {
+                    // commands where every parameter has a default may
+                    // be activated without any
+                    let params = params.as_deref().unwrap_or("{}");
+                    let params: P = serde_json::from_str(params)
+                        .with_context(|| format!("invalid parameters for command {name}"))?;
+                    callback(params).await
+                })
+            }),
+        );
         self
     }
 }
 
 #[derive(Debug, Clone)]
 pub enum Icon {
+    /// A named icon, taken from the system.
     Name(String),
+    /// Just some text. It should be very short.
     Text(String),
+    /// Absolute path to an image file.
+    File(PathBuf),
+    /// An image encoded inline.
+    ///
+    /// Covey ignores images over its size limits, which are documented on
+    /// `Icon::MAX_IMAGE_BYTES` and `Icon::MAX_LIST_IMAGE_BYTES` in the `covey`
+    /// crate, so this should only be used for small images like thumbnails.
+    /// Prefer [`Icon::File`] if the image is already saved somewhere.
+    Image {
+        data: Vec<u8>,
+        /// MIME type of the data, e.g. `"image/png"`.
+        mime_type: String,
+    },
 }
 
 impl Icon {
+    /// An inline PNG image.
+    pub fn png(data: impl Into<Vec<u8>>) -> Self {
+        Self::Image {
+            data: data.into(),
+            mime_type: String::from("image/png"),
+        }
+    }
+
+    /// An inline SVG image.
+    pub fn svg(data: impl Into<Vec<u8>>) -> Self {
+        Self::Image {
+            data: data.into(),
+            mime_type: String::from("image/svg+xml"),
+        }
+    }
+
     pub(crate) fn into_proto(self) -> proto::list_item::Icon {
         use proto::list_item::Icon as Proto;
         match self {
             Self::Name(name) => Proto::Name(name),
             Self::Text(text) => Proto::Text(text),
+            Self::File(path) => Proto::File(path.to_string_lossy().into_owned()),
+            Self::Image { data, mime_type } => Proto::Image(proto::Image { data, mime_type }),
+        }
+    }
+}
+
+/// Small extra information shown next to a list item, like a git branch,
+/// a status, a date or a count.
+///
+/// Keep these short: there is only a little room next to the title.
+#[derive(Debug, Clone)]
+pub enum Accessory {
+    Text(String),
+    /// Text with an icon before it.
+    IconText {
+        icon: Icon,
+        text: String,
+    },
+    /// Text with a coloured background.
+    Tag {
+        text: String,
+        color: TagColor,
+    },
+}
+
+impl Accessory {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::Text(text.into())
+    }
+
+    pub fn icon_text(icon: Icon, text: impl Into<String>) -> Self {
+        Self::IconText {
+            icon,
+            text: text.into(),
+        }
+    }
+
+    pub fn tag(text: impl Into<String>, color: TagColor) -> Self {
+        Self::Tag {
+            text: text.into(),
+            color,
+        }
+    }
+
+    pub(crate) fn into_proto(self) -> proto::Accessory {
+        use proto::accessory::Kind;
+
+        let kind = match self {
+            Self::Text(text) => Kind::Text(text),
+            Self::IconText { icon, text } => {
+                use proto::icon_text::Icon as Proto;
+                let icon = match icon.into_proto() {
+                    proto::list_item::Icon::Name(name) => Proto::Name(name),
+                    proto::list_item::Icon::Text(text) => Proto::Text(text),
+                    proto::list_item::Icon::File(path) => Proto::File(path),
+                    proto::list_item::Icon::Image(image) => Proto::Image(image),
+                };
+                Kind::IconText(proto::IconText {
+                    icon: Some(icon),
+                    text,
+                })
+            }
+            Self::Tag { text, color } => Kind::Tag(proto::Tag {
+                text,
+                color: Some(color.into_proto().into()),
+            }),
+        };
+
+        proto::Accessory { kind: Some(kind) }
+    }
+}
+
+/// Colour of an [`Accessory::Tag`].
+///
+/// The exact colours are chosen by the frontend to fit its theme.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TagColor {
+    #[default]
+    Default,
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+    Grey,
+}
+
+impl TagColor {
+    fn into_proto(self) -> proto::TagColor {
+        match self {
+            Self::Default => proto::TagColor::Default,
+            Self::Red => proto::TagColor::Red,
+            Self::Orange => proto::TagColor::Orange,
+            Self::Yellow => proto::TagColor::Yellow,
+            Self::Green => proto::TagColor::Green,
+            Self::Blue => proto::TagColor::Blue,
+            Self::Purple => proto::TagColor::Purple,
+            Self::Grey => proto::TagColor::Grey,
         }
     }
 }
 
-type DynFuture<T> = Pin<Box<dyn Future<Output = T> + Send + Sync>>;
+pub(crate) type DynFuture<T> = Pin<Box<dyn Future<Output = T> + Send + Sync>>;
 type ActivationFunction = Arc<dyn Fn() -> DynFuture<Result<Actions>> + Send + Sync>;
+/// Called with the command's parameters as a JSON object, if it has any.
+type CommandFunction = Arc<dyn Fn(Option<String>) -> DynFuture<Result<Actions>> + Send + Sync>;
+
+/// A command added to a single list item with
+/// [`ListItem::add_dynamic_command`].
+#[derive(Clone)]
+pub struct DynamicCommand {
+    title: String,
+    hotkey: Option<HotkeySequence>,
+    callback: ActivationFunction,
+}
+
+impl DynamicCommand {
+    pub fn new<Fut>(
+        title: impl Into<String>,
+        callback: impl Fn() -> Fut + Send + Sync + 'static,
+    ) -> Self
+    where
+        Fut: Future<Output = Result<Actions>> + Send + Sync + 'static,
+    {
+        Self {
+            title: title.into(),
+            hotkey: None,
+            callback: Arc::new(move || Box::pin(callback())),
+        }
+    }
+
+    /// Sets the hotkey that activates this command, which can be a
+    /// [`Hotkey`](crate::Hotkey) or a [`HotkeySequence`].
+    #[must_use = "builder method consumes self"]
+    pub fn with_hotkey(mut self, hotkey: impl Into<HotkeySequence>) -> Self {
+        self.hotkey = Some(hotkey.into());
+        self
+    }
+
+    /// Manifest command IDs can't start with `#`, so this can't clash
+    /// with them.
+    fn id(index: usize) -> String {
+        format!("#{index}")
+    }
+}
+
+impl std::fmt::Debug for DynamicCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DynamicCommand")
+            .field("title", &self.title)
+            .field("hotkey", &self.hotkey)
+            .finish_non_exhaustive()
+    }
+}
 
 #[derive(Clone)]
 pub(crate) struct ListItemCallbacks {
     /// Key is the command's ID.
-    commands: HashMap<&'static str, ActivationFunction>,
-    item_title: String,
+    commands: HashMap<&'static str, CommandFunction>,
+    /// Commands not in the manifest, identified by their index.
+    dynamic: Vec<DynamicCommand>,
+    pub(crate) preview: Option<PreviewFunction>,
+    /// Title and key of the item, set when the item is stored.
+    pub(crate) item_title: String,
+    pub(crate) item_key: Option<String>,
 }
 
 impl ListItemCallbacks {
-    pub(crate) fn new(title: String) -> Self {
+    pub(crate) fn new() -> Self {
         Self {
             commands: HashMap::default(),
-            item_title: title,
+            dynamic: vec![],
+            preview: None,
+            item_title: String::new(),
+            item_key: None,
         }
     }
 
-    pub(crate) fn add_command(&mut self, name: &'static str, callback: ActivationFunction) {
+    pub(crate) fn add_command(&mut self, name: &'static str, callback: CommandFunction) {
         self.commands.insert(name, callback);
     }
 
     /// Calls a command by name, returning an empty vec if the command is not found.
-    pub(crate) async fn call_command(&self, name: &str) -> Result<Vec<Action>> {
-        if let Some(cmd) = self.commands.get(name) {
-            crate::rank::register_usage(&self.item_title);
-            cmd().await.map(|actions| actions.list)
+    ///
+    /// `params` is a JSON object of the command's parameters, if it has any.
+    pub(crate) async fn call_command(
+        &self,
+        name: &str,
+        params: Option<String>,
+    ) -> Result<Vec<Action>> {
+        let future = if let Some(cmd) = self.commands.get(name) {
+            cmd(params)
+        } else if let Some(dynamic) = self
+            .dynamic
+            .iter()
+            .enumerate()
+            .find(|(i, _)| DynamicCommand::id(*i) == name)
+            .map(|(_, dynamic)| dynamic)
+        {
+            (dynamic.callback)()
         } else {
-            Ok(vec![])
-        }
+            return Ok(vec![]);
+        };
+
+        crate::rank::register_usage(self.item_key.as_deref(), &self.item_title);
+        future.await.map(|actions| actions.list)
     }
 
     pub(crate) fn ids(&self) -> impl Iterator<Item = &'static str> + use<'_> {
         self.commands.keys().copied()
     }
+
+    pub(crate) fn dynamic_protos(&self) -> Vec<proto::DynamicCommand> {
+        self.dynamic
+            .iter()
+            .enumerate()
+            .map(|(i, dynamic)| proto::DynamicCommand {
+                id: DynamicCommand::id(i),
+                title: dynamic.title.clone(),
+                hotkey: dynamic.hotkey.as_ref().map(ToString::to_string),
+            })
+            .collect()
+    }
 }
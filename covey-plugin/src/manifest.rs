@@ -97,6 +97,18 @@ mod tests {
                 title = "Yet another selection"
                 type.selection.allowed-values = ["oaiwrha", "iosdg"]
                 type.selection.default = "iosdg"
+
+                [[commands]]
+                id = "activate"
+                title = "Activate"
+
+                [[commands]]
+                id = "rename-to"
+                title = "Rename to"
+                parameters = [
+                    { id = "new-name", title = "New name", type.text = { min-length = 1 } },
+                    { id = "overwrite", title = "Overwrite", type.bool = { default = false } },
+                ]
             "#
         );
     }
@@ -117,7 +129,29 @@ mod tests {
         };
 
         use config::CommandExt;
-        crate::ListItem::new("ajwroiajw").on_activate(|| async { Ok(vec![]) });
+        crate::ListItem::new("ajwroiajw")
+            .on_activate(|| async { Ok(vec![]) })
+            .on_rename_to(
+                |params: config::command_params::rename_to::Params| async move {
+                    let _: (String, bool) = (params.new_name, params.overwrite);
+                    Ok(vec![])
+                },
+            );
+    }
+
+    #[test]
+    fn deserialize_command_params() {
+        let input = serde_json::json!({ "new-name": "file.txt" });
+
+        let deserialized: config::command_params::rename_to::Params =
+            serde_json::from_str(&input.to_string()).unwrap();
+        assert_eq!(
+            deserialized,
+            config::command_params::rename_to::Params {
+                new_name: "file.txt".to_string(),
+                overwrite: false,
+            }
+        );
     }
 
     #[test]
@@ -0,0 +1,83 @@
+use std::{fmt, future::Future, sync::Arc};
+
+use anyhow::Result;
+
+use crate::{list::DynFuture, proto, Input, List};
+
+pub(crate) type PageQueryFunction = Arc<dyn Fn(String) -> DynFuture<Result<List>> + Send + Sync>;
+
+/// A page with its own list, shown on top of the plugin's main list.
+///
+/// Open a page with [`Action::PushPage`]. While the page is open, all
+/// queries are sent to the page's query function instead of
+/// [`Plugin::query`]. The user can go back to the previous page with
+/// escape, or backspace on an empty input.
+///
+/// Pages can be nested: activating a list item in a page can push
+/// another page.
+///
+/// [`Action::PushPage`]: crate::Action::PushPage
+/// [`Plugin::query`]: crate::Plugin::query
+#[derive(Clone)]
+pub struct Page {
+    pub title: Option<String>,
+    /// Placeholder of the input when it is empty.
+    pub placeholder: Option<String>,
+    /// Initial input when the page is opened.
+    pub input: Input,
+    pub(crate) query: PageQueryFunction,
+}
+
+impl Page {
+    /// Creates a page that calls `query` whenever the input changes.
+    ///
+    /// The query is the entire input, without the plugin's prefix.
+    pub fn new<Fut>(query: impl Fn(String) -> Fut + Send + Sync + 'static) -> Self
+    where
+        Fut: Future<Output = Result<List>> + Send + Sync + 'static,
+    {
+        Self {
+            title: None,
+            placeholder: None,
+            input: Input::new(""),
+            query: Arc::new(move |q| Box::pin(query(q))),
+        }
+    }
+
+    #[must_use = "builder method consumes self"]
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    #[must_use = "builder method consumes self"]
+    pub fn with_placeholder(mut self, placeholder: impl Into<String>) -> Self {
+        self.placeholder = Some(placeholder.into());
+        self
+    }
+
+    #[must_use = "builder method consumes self"]
+    pub fn with_input(mut self, input: Input) -> Self {
+        self.input = input;
+        self
+    }
+
+    pub(crate) fn into_proto(self, id: u64) -> proto::Page {
+        proto::Page {
+            id,
+            title: self.title,
+            placeholder: self.placeholder,
+            input: self.input.into_proto(),
+        }
+    }
+}
+
+impl fmt::Debug for Page {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Page")
+            .field("title", &self.title)
+            .field("placeholder", &self.placeholder)
+            .field("input", &self.input)
+            .finish_non_exhaustive()
+    }
+}
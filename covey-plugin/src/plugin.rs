@@ -1,7 +1,8 @@
 use std::future::Future;
 
 use crate::{
-    manifest::ManifestDeserialization, plugin_lock::ServerState, proto, Action, List, Result,
+    manifest::ManifestDeserialization, plugin_lock::ServerState, proto, Action, List, Output,
+    Result,
 };
 
 pub trait Plugin: Sized + Send + Sync + 'static {
@@ -43,15 +44,27 @@ where
         &self,
         request: tonic::Request<proto::QueryRequest>,
     ) -> TonicResult<proto::QueryResponse> {
-        let list = self
-            .plugin
-            .read()
-            .await
-            .as_ref()
-            .expect("plugin has not been initialised")
-            .query(request.into_inner().query)
-            .await
-            .map_err(into_tonic_status)?;
+        let request = request.into_inner();
+
+        let list = if let Some(page_id) = request.page_id {
+            let query = self
+                .page_store
+                .lock()
+                .get(page_id)
+                .ok_or(tonic::Status::data_loss(format!(
+                    "failed to fetch query function of page with id {page_id}"
+                )))?;
+            query(request.query).await
+        } else {
+            self.plugin
+                .read()
+                .await
+                .as_ref()
+                .expect("plugin has not been initialised")
+                .query(request.query)
+                .await
+        }
+        .map_err(into_tonic_status)?;
 
         Ok(tonic::Response::new(
             self.list_item_store.lock().store_query_result(list),
@@ -73,17 +86,75 @@ where
                 )))?;
 
         let response = callbacks
-            .call_command(&request.command_name)
+            .call_command(&request.command_name, request.parameters)
             .await
-            .map(|a| proto::ActivationResponse {
-                actions: a.into_iter().map(Action::into_proto).collect(),
-            });
+            .map(|actions| self.actions_response(actions));
+
+        match response {
+            Ok(response) => Ok(tonic::Response::new(response)),
+            Err(err) => Err(into_tonic_status(err)),
+        }
+    }
+
+    async fn handle_output(
+        &self,
+        request: tonic::Request<proto::OutputRequest>,
+    ) -> TonicResult<proto::ActivationResponse> {
+        let request = request.into_inner();
+        let id = request.callback_id;
+        let callback = self
+            .output_store
+            .lock()
+            .take(id)
+            .ok_or(tonic::Status::data_loss(format!(
+                "failed to fetch output callback with id {id}"
+            )))?;
+
+        let response = callback(Output {
+            stdout: request.stdout,
+            stderr: request.stderr,
+        })
+        .await
+        .map(|actions| self.actions_response(actions.list));
 
         match response {
             Ok(response) => Ok(tonic::Response::new(response)),
             Err(err) => Err(into_tonic_status(err)),
         }
     }
+
+    async fn preview(
+        &self,
+        request: tonic::Request<proto::PreviewRequest>,
+    ) -> TonicResult<proto::PreviewResponse> {
+        let id = request.into_inner().selection_id;
+        let Some(preview) = self.list_item_store.lock().fetch_preview_of(id) else {
+            return Ok(tonic::Response::new(proto::PreviewResponse {
+                preview: None,
+            }));
+        };
+
+        match preview().await {
+            Ok(preview) => Ok(tonic::Response::new(proto::PreviewResponse {
+                preview: Some(preview.into_proto()),
+            })),
+            Err(err) => Err(into_tonic_status(err)),
+        }
+    }
+}
+
+impl<T> ServerState<T> {
+    fn actions_response(&self, actions: Vec<Action>) -> proto::ActivationResponse {
+        let mut pages = self.page_store.lock();
+        let mut outputs = self.output_store.lock();
+
+        proto::ActivationResponse {
+            actions: actions
+                .into_iter()
+                .map(|action| action.into_proto(&mut pages, &mut outputs))
+                .collect(),
+        }
+    }
 }
 
 #[expect(
@@ -1,11 +1,16 @@
 use parking_lot::Mutex;
 use tokio::sync::RwLock;
 
-use crate::{store::ListItemStore, Plugin};
+use crate::{
+    store::{ListItemStore, OutputStore, PageStore},
+    Plugin,
+};
 
 pub(crate) struct ServerState<P> {
     pub(crate) plugin: RwLock<Option<P>>,
     pub(crate) list_item_store: Mutex<ListItemStore>,
+    pub(crate) page_store: Mutex<PageStore>,
+    pub(crate) output_store: Mutex<OutputStore>,
 }
 
 impl<T: Plugin> ServerState<T> {
@@ -13,6 +18,8 @@ impl<T: Plugin> ServerState<T> {
         Self {
             plugin: RwLock::new(None),
             list_item_store: Mutex::new(ListItemStore::new()),
+            page_store: Mutex::new(PageStore::new()),
+            output_store: Mutex::new(OutputStore::new()),
         }
     }
 }
@@ -0,0 +1,49 @@
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::Result;
+
+use crate::{list::DynFuture, proto};
+
+pub(crate) type PreviewFunction = Arc<dyn Fn() -> DynFuture<Result<Preview>> + Send + Sync>;
+
+/// A larger preview of a list item, shown next to the list.
+///
+/// Add one to a list item with [`ListItem::on_preview`]. Previews are only
+/// requested when the item is selected.
+///
+/// [`ListItem::on_preview`]: crate::ListItem::on_preview
+#[derive(Debug, Clone)]
+pub enum Preview {
+    Markdown(String),
+    Code {
+        code: String,
+        /// Language to highlight the code with, e.g. `"rust"`.
+        language: Option<String>,
+    },
+    /// Absolute path to an image file.
+    Image(PathBuf),
+    /// A table of key/value pairs, shown in order.
+    Metadata(Vec<(String, String)>),
+}
+
+impl Preview {
+    pub(crate) fn into_proto(self) -> proto::Preview {
+        use proto::preview::Content;
+
+        let content = match self {
+            Self::Markdown(markdown) => Content::Markdown(markdown),
+            Self::Code { code, language } => Content::Code(proto::CodeBlock { code, language }),
+            Self::Image(path) => Content::ImagePath(path.to_string_lossy().into_owned()),
+            Self::Metadata(entries) => Content::Metadata(proto::Metadata {
+                entries: entries
+                    .into_iter()
+                    .map(|(key, value)| proto::MetadataEntry { key, value })
+                    .collect(),
+            }),
+        };
+
+        proto::Preview {
+            content: Some(content),
+        }
+    }
+}
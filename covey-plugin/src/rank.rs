@@ -1,10 +1,26 @@
 //! Rank items based on query and usage.
 //!
-//! Usage stats are stored in [`DATA_DIR`]/activations.json.
+//! Usage stats are stored in [`DATA_DIR`]/activations.json, by the
+//! [key](ListItem::key) of each item.
+//!
+//! The file is read once, and activations are kept in memory. They are
+//! written a short time after an item is activated, so that several
+//! activations in a row are written together, and when the server shuts
+//! down. Other processes of the same plugin may write the file too, so it
+//! is locked while writing, and their activations are kept.
 
-use std::{collections::HashMap, io::Read, path::PathBuf};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io, mem,
+    ops::Range,
+    path::PathBuf,
+    sync::LazyLock,
+    time::Duration,
+};
 
 use az::SaturatingAs;
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 
@@ -14,12 +30,181 @@ fn activations_path() -> PathBuf {
     crate::plugin_data_dir().join("activations.json")
 }
 
+/// How long to wait after an activation before writing the file.
+const WRITE_DELAY: Duration = Duration::from_secs(1);
+
+/// Default of [`forget_after`].
+const DEFAULT_FORGET_AFTER: Duration = Duration::from_hours(90 * 24);
+
+static STORE: LazyLock<Mutex<Store>> = LazyLock::new(|| Mutex::new(Store::load()));
+
+/// Activations of this plugin's items, shared by every query.
+struct Store {
+    activations: AllActivations,
+    /// Activations that haven't been written to the file yet, in order.
+    pending: Vec<PendingActivation>,
+    /// Whether a write will happen after [`WRITE_DELAY`].
+    write_scheduled: bool,
+    forget_after: Duration,
+}
+
+struct PendingActivation {
+    key: Option<String>,
+    title: String,
+    time: OffsetDateTime,
+}
+
+impl Store {
+    fn load() -> Self {
+        let mut activations = read_activations().unwrap_or_else(|e| {
+            eprintln!("error reading activations: {e}");
+            AllActivations::default()
+        });
+        activations.prune(OffsetDateTime::now_utc(), DEFAULT_FORGET_AFTER);
+
+        Self {
+            activations,
+            pending: vec![],
+            write_scheduled: false,
+            forget_after: DEFAULT_FORGET_AFTER,
+        }
+    }
+}
+
+/// Forgets items that haven't been activated for `duration`.
+///
+/// The default is 90 days. This should be called before any items are
+/// ranked, e.g. when the plugin is initialised.
+pub fn forget_after(duration: Duration) {
+    let mut store = STORE.lock();
+    store.forget_after = duration;
+    store.activations.prune(OffsetDateTime::now_utc(), duration);
+}
+
+/// Reads the file, which is empty if it doesn't exist yet.
+fn read_activations() -> io::Result<AllActivations> {
+    match fs::read(activations_path()) {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(AllActivations::default()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Writes activations that haven't been written yet, instead of waiting
+/// for the scheduled write.
+///
+/// This is called when the server shuts down, as scheduled writes don't
+/// happen once the runtime has stopped.
+pub(crate) fn flush() {
+    write_pending();
+}
+
+/// Writes pending activations to the file.
+///
+/// This blocks while another process is writing the file.
+fn write_pending() {
+    let (pending, forget_after) = {
+        let mut store = STORE.lock();
+        store.write_scheduled = false;
+        (mem::take(&mut store.pending), store.forget_after)
+    };
+    if pending.is_empty() {
+        return;
+    }
+
+    match write_with(&pending, forget_after) {
+        Ok(mut activations) => {
+            let mut store = STORE.lock();
+            // items may have been activated while writing
+            for activation in &store.pending {
+                activations.register(
+                    activation.key.as_deref(),
+                    &activation.title,
+                    activation.time,
+                );
+            }
+            store.activations = activations;
+        }
+        Err(e) => {
+            eprintln!("error writing activations: {e}");
+            // try again with the next activation
+            STORE.lock().pending.splice(0..0, pending);
+        }
+    }
+}
+
+/// Adds activations to the file, returning everything in the file.
+fn write_with(pending: &[PendingActivation], forget_after: Duration) -> io::Result<AllActivations> {
+    let path = activations_path();
+    let lock = File::create(path.with_extension("json.lock"))?;
+    lock.lock()?;
+
+    // start from the file, as other processes may have written to it
+    let mut activations = read_activations()?;
+    for activation in pending {
+        activations.register(
+            activation.key.as_deref(),
+            &activation.title,
+            activation.time,
+        );
+    }
+    activations.prune(OffsetDateTime::now_utc(), forget_after);
+
+    // write to another file first, so the file is never half written
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(&temp_path, serde_json::to_vec(&activations)?)?;
+    fs::rename(temp_path, path)?;
+
+    lock.unlock()?;
+    Ok(activations)
+}
+
 #[derive(Debug, Serialize, Deserialize, Default)]
 #[serde(transparent, default)]
 struct AllActivations {
     map: HashMap<String, ItemActivations>,
 }
 
+impl AllActivations {
+    /// Activations of an item.
+    ///
+    /// Items used to be stored by their title, so an item with a key that
+    /// hasn't been activated since then has the activations of its title.
+    fn get(&self, key: Option<&str>, title: &str) -> Option<&ItemActivations> {
+        key.and_then(|key| self.map.get(key))
+            .or_else(|| self.map.get(title))
+    }
+
+    /// Adds an activation of an item.
+    ///
+    /// If the item has a key, activations stored by its title are moved to
+    /// the key.
+    fn register(&mut self, key: Option<&str>, title: &str, now: OffsetDateTime) {
+        let stored_key = key.unwrap_or(title);
+        if !self.map.contains_key(stored_key) {
+            if let Some(migrated) = key.and_then(|_| self.map.remove(title)) {
+                self.map.insert(stored_key.to_owned(), migrated);
+            }
+        }
+
+        let entry = self
+            .map
+            .entry(stored_key.to_owned())
+            .or_insert_with(|| ItemActivations {
+                frequency: 0,
+                last_use: now,
+            });
+        entry.frequency += 1;
+        entry.last_use = now;
+    }
+
+    /// Removes items that haven't been activated for `forget_after`.
+    fn prune(&mut self, now: OffsetDateTime, forget_after: Duration) {
+        self.map
+            .retain(|_, activations| now - activations.last_use < forget_after);
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct ItemActivations {
     frequency: u64,
@@ -27,45 +212,27 @@ struct ItemActivations {
     last_use: time::OffsetDateTime,
 }
 
-fn activations() -> Option<AllActivations> {
-    let mut file = std::fs::OpenOptions::new()
-        .create(true)
-        .write(true)
-        .truncate(false)
-        .read(true)
-        .open(activations_path())
-        .inspect_err(|e| eprintln!("error: {e}"))
-        .ok()?;
-
-    let mut buf = Vec::new();
-    file.read_to_end(&mut buf).ok()?;
-
-    serde_json::from_slice(&buf).ok()
-}
-
-pub(crate) fn register_usage(title: &str) {
-    let mut current = activations().unwrap_or_default();
-    println!("current {current:?}");
+/// Adds an activation of an item, which is written to the file soon.
+///
+/// This must be called within a tokio runtime.
+pub(crate) fn register_usage(key: Option<&str>, title: &str) {
+    let now = OffsetDateTime::now_utc();
+    let mut store = STORE.lock();
+    store.activations.register(key, title, now);
+    store.pending.push(PendingActivation {
+        key: key.map(str::to_owned),
+        title: title.to_owned(),
+        time: now,
+    });
 
-    // increment usage
-    let entry = current
-        .map
-        .entry(title.to_string())
-        .or_insert_with(|| ItemActivations {
-            frequency: 0,
-            last_use: OffsetDateTime::now_utc(),
+    if !mem::replace(&mut store.write_scheduled, true) {
+        tokio::spawn(async {
+            tokio::time::sleep(WRITE_DELAY).await;
+            if let Err(e) = tokio::task::spawn_blocking(write_pending).await {
+                eprintln!("error writing activations: {e}");
+            }
         });
-
-    entry.frequency += 1;
-    entry.last_use = OffsetDateTime::now_utc();
-
-    // write to file
-    let Ok(json_string) = serde_json::to_string(&current) else {
-        eprintln!("failed stringifying {current:?}");
-        return;
-    };
-    eprintln!("writing {json_string} to {:?}", activations_path());
-    _ = std::fs::write(activations_path(), json_string);
+    }
 }
 
 pub async fn rank<'iter>(
@@ -74,11 +241,7 @@ pub async fn rank<'iter>(
     weights: Weights,
 ) -> Vec<ListItem> {
     let should_track_history = weights.frequency != 0.0 || weights.recency != 0.0;
-    let activations = if should_track_history {
-        self::activations().unwrap_or_else(AllActivations::default)
-    } else {
-        AllActivations::default()
-    };
+    let store = should_track_history.then(|| STORE.lock());
 
     let now = OffsetDateTime::now_utc();
 
@@ -91,26 +254,30 @@ pub async fn rank<'iter>(
                     (weights.$field != 0.0)
                         .then(|| sublime_fuzzy::best_match(&query, &item.$field))
                         .flatten()
-                        .map(|m| m.score() as f32 * weights.$field)
-                        .unwrap_or(0.0)
+                        .map_or((0.0, vec![]), |m| {
+                            (m.score() as f32 * weights.$field, matched_ranges(&m))
+                        })
                 };
             }
 
-            let title_score = score!(title);
-            let desc_score = score!(description);
-
-            let (freq, elapsed_secs) = activations.map.get(&item.title).map_or(
-                (0, u64::MAX),
-                |ItemActivations {
-                     frequency,
-                     last_use,
-                 }| {
-                    (
-                        *frequency,
-                        (now - *last_use).whole_seconds().saturating_as::<u64>(),
-                    )
-                },
-            );
+            let (title_score, title_highlights) = score!(title);
+            let (desc_score, description_highlights) = score!(description);
+
+            let (freq, elapsed_secs) = store
+                .as_ref()
+                .and_then(|store| store.activations.get(item.key.as_deref(), &item.title))
+                .map_or(
+                    (0, u64::MAX),
+                    |ItemActivations {
+                         frequency,
+                         last_use,
+                     }| {
+                        (
+                            *frequency,
+                            (now - *last_use).whole_seconds().saturating_as::<u64>(),
+                        )
+                    },
+                );
 
             let elapsed_min = elapsed_secs / 1000;
             // between (0, 1]
@@ -124,12 +291,26 @@ pub async fn rank<'iter>(
 
             let total_score = fuzzy_score + freq_score + recency_score;
             let should_show = query.is_empty() || fuzzy_score > 1.0;
-            should_show.then_some((total_score, item))
+            should_show.then(|| {
+                let item = ListItem {
+                    title_highlights,
+                    description_highlights,
+                    ..item.clone()
+                };
+                (total_score, item)
+            })
         })
         .collect();
     // sort reversed
     scored.sort_by(|(s1, _), (s2, _)| s2.total_cmp(s1));
-    scored.into_iter().map(|(_, item)| item).cloned().collect()
+    scored.into_iter().map(|(_, item)| item).collect()
+}
+
+/// Character ranges of the target that were matched.
+fn matched_ranges(m: &sublime_fuzzy::Match) -> Vec<Range<usize>> {
+    m.continuous_matches()
+        .map(|c| c.start()..c.start() + c.len())
+        .collect()
 }
 
 pub struct Weights {
@@ -179,3 +360,49 @@ impl Weights {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration as StdDuration;
+
+    use time::{Duration, OffsetDateTime};
+
+    use super::AllActivations;
+
+    #[test]
+    fn keys_take_over_activations_of_their_title() {
+        let now = OffsetDateTime::UNIX_EPOCH + Duration::days(1000);
+        let mut activations = AllActivations::default();
+        activations.register(None, "Firefox", now);
+        activations.register(None, "Firefox", now);
+
+        assert_eq!(
+            activations
+                .get(Some("firefox.desktop"), "Firefox")
+                .map(|a| a.frequency),
+            Some(2),
+            "keys fall back to the title until they are activated"
+        );
+
+        activations.register(Some("firefox.desktop"), "Firefox", now);
+        assert_eq!(activations.map["firefox.desktop"].frequency, 3);
+        assert!(!activations.map.contains_key("Firefox"));
+
+        // another item with the same title no longer shares the history
+        activations.register(Some("firefox-nightly.desktop"), "Firefox", now);
+        assert_eq!(activations.map["firefox-nightly.desktop"].frequency, 1);
+        assert_eq!(activations.map["firefox.desktop"].frequency, 3);
+    }
+
+    #[test]
+    fn unused_items_are_pruned() {
+        let now = OffsetDateTime::UNIX_EPOCH + Duration::days(1000);
+        let mut activations = AllActivations::default();
+        activations.register(None, "Old", now - Duration::days(30));
+        activations.register(None, "New", now - Duration::days(1));
+
+        activations.prune(now, StdDuration::from_hours(7 * 24));
+        assert!(!activations.map.contains_key("Old"));
+        assert!(activations.map.contains_key("New"));
+    }
+}
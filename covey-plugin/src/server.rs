@@ -26,13 +26,19 @@ pub fn run_server<T: Plugin>(plugin_id: &'static str) -> ! {
 
                 Server::builder()
                     .add_service(PluginServer::new(ServerState::<T>::new_empty()))
-                    .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+                    .serve_with_incoming_shutdown(
+                        tokio_stream::wrappers::TcpListenerStream::new(listener),
+                        shutdown_signal(),
+                    )
                     .await?;
 
                 Ok(())
             })
         });
 
+    // the runtime has stopped, so scheduled writes won't happen
+    crate::rank::flush();
+
     match result {
         Ok(()) => process::exit(0),
         Err(e) => {
@@ -42,6 +48,38 @@ pub fn run_server<T: Plugin>(plugin_id: &'static str) -> ! {
     }
 }
 
+/// Resolves once the process is asked to stop, with Ctrl+C or SIGTERM.
+///
+/// A plugin that is killed can't do anything before stopping.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            eprintln!("error listening for ctrl+c: {e}");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                eprintln!("error listening for SIGTERM: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {}
+        () = terminate => {}
+    }
+}
+
 fn print_error(e: &anyhow::Error) {
     let err_string = e
         .chain()
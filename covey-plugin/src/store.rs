@@ -5,9 +5,12 @@ use std::{
     sync::atomic::{AtomicU64, Ordering},
 };
 
-use az::CheckedAs;
+use az::{CheckedAs, SaturatingAs};
 
-use crate::{list::ListItemCallbacks, proto, Icon, List, ListItem, ListStyle};
+use crate::{
+    capture::OutputFunction, list::ListItemCallbacks, page::PageQueryFunction,
+    preview::PreviewFunction, proto, Accessory, Icon, List, ListItem, ListStyle,
+};
 
 /// Store to map list item IDs to their callbacks.
 pub(crate) struct ListItemStore {
@@ -36,11 +39,22 @@ impl ListItemStore {
     /// Stores the result of a query, returning the response that should be
     /// sent to covey.
     pub(crate) fn store_query_result(&mut self, list: List) -> proto::QueryResponse {
+        let sections = list
+            .sections
+            .into_iter()
+            .map(|(title, start)| proto::Section {
+                title,
+                start: start.saturating_as(),
+            })
+            .collect();
+
         // Don't store an empty result
         if list.items.is_empty() {
             return proto::QueryResponse {
                 items: vec![],
                 list_style: list.style.map(ListStyle::into_proto),
+                sections,
+                private: Some(list.private),
             };
         }
 
@@ -54,6 +68,8 @@ impl ListItemStore {
         return proto::QueryResponse {
             items,
             list_style: list.style.map(ListStyle::into_proto),
+            sections,
+            private: Some(list.private),
         };
 
         fn split_item_vec(
@@ -66,14 +82,28 @@ impl ListItemStore {
             let mut callbacks = vec![];
 
             for (id, item) in iter::zip(new_ids, vec) {
+                let mut commands = item.commands;
+                commands.item_title.clone_from(&item.title);
+                commands.item_key.clone_from(&item.key);
+
                 items.push(proto::ListItem {
                     id,
+                    key: item.key,
                     title: item.title,
                     description: item.description,
                     icon: item.icon.map(Icon::into_proto),
-                    available_commands: item.commands.ids().map(|s| s.to_owned()).collect(),
+                    available_commands: commands.ids().map(|s| s.to_owned()).collect(),
+                    dynamic_commands: commands.dynamic_protos(),
+                    has_preview: Some(commands.preview.is_some()),
+                    title_highlights: highlights_into_proto(item.title_highlights),
+                    description_highlights: highlights_into_proto(item.description_highlights),
+                    accessories: item
+                        .accessories
+                        .into_iter()
+                        .map(Accessory::into_proto)
+                        .collect(),
                 });
-                callbacks.push(item.commands);
+                callbacks.push(commands);
             }
 
             (items, callbacks)
@@ -102,6 +132,28 @@ impl ListItemStore {
 
         Some(found_callback)
     }
+
+    /// Finds the preview function of an ID.
+    ///
+    /// Unlike [`Self::fetch_callbacks_of`], this does not remove any old
+    /// queries, since previewing an item does not mean that the list has
+    /// changed.
+    pub(crate) fn fetch_preview_of(&self, id: u64) -> Option<PreviewFunction> {
+        self.queries
+            .iter()
+            .find_map(|query| query.callback_of_id(id))
+            .and_then(|callbacks| callbacks.preview.clone())
+    }
+}
+
+fn highlights_into_proto(ranges: Vec<Range<usize>>) -> Vec<proto::Highlight> {
+    ranges
+        .into_iter()
+        .map(|range| proto::Highlight {
+            start: range.start.saturating_as(),
+            end: range.end.saturating_as(),
+        })
+        .collect()
 }
 
 /// INVARIANTS:
@@ -114,7 +166,8 @@ struct QueryListItemStore {
 
 impl QueryListItemStore {
     pub fn callback_of_id(&self, id: u64) -> Option<&ListItemCallbacks> {
-        let offset = id - self.first_id;
+        // IDs of older queries are before the first ID
+        let offset = id.checked_sub(self.first_id)?;
         self.callbacks.get(
             offset
                 .checked_as::<usize>()
@@ -123,6 +176,69 @@ impl QueryListItemStore {
     }
 }
 
+/// Store to map page IDs to their query functions.
+///
+/// The frontend may keep a page open for any amount of time, so pages are
+/// only dropped once there are too many. Page stacks are not expected to
+/// get anywhere near this deep.
+pub(crate) type PageStore = CallbackStore<PageQueryFunction>;
+
+/// Store to map output callback IDs to their callbacks.
+///
+/// Commands that fail never have their callback called, so callbacks are
+/// also dropped once there are too many.
+pub(crate) type OutputStore = CallbackStore<OutputFunction>;
+
+/// Store to map IDs to callbacks, which only keeps the newest
+/// [`Self::MAX_CALLBACKS`].
+pub(crate) struct CallbackStore<F> {
+    /// Oldest first.
+    callbacks: VecDeque<(u64, F)>,
+    ids: AutoIncrementer,
+}
+
+impl<F: Clone> CallbackStore<F> {
+    const MAX_CALLBACKS: usize = 64;
+
+    pub(crate) fn new() -> Self {
+        Self {
+            callbacks: VecDeque::new(),
+            ids: AutoIncrementer(AtomicU64::new(0)),
+        }
+    }
+
+    /// Stores a callback, returning it's ID.
+    ///
+    /// The oldest callback is dropped if there are too many.
+    pub(crate) fn store(&mut self, callback: F) -> u64 {
+        let id = self.ids.fetch_many(1).start;
+
+        if self.callbacks.len() >= Self::MAX_CALLBACKS {
+            self.callbacks.pop_front();
+        }
+        self.callbacks.push_back((id, callback));
+
+        id
+    }
+
+    /// Finds a callback, keeping it stored.
+    pub(crate) fn get(&self, id: u64) -> Option<F> {
+        self.callbacks
+            .iter()
+            .find(|(callback_id, _)| *callback_id == id)
+            .map(|(_, callback)| callback.clone())
+    }
+
+    /// Removes and returns a callback.
+    pub(crate) fn take(&mut self, id: u64) -> Option<F> {
+        let index = self
+            .callbacks
+            .iter()
+            .position(|(callback_id, _)| *callback_id == id)?;
+        self.callbacks.remove(index).map(|(_, callback)| callback)
+    }
+}
+
 /// Unique ID generator by incrementing numbers.
 struct AutoIncrementer(AtomicU64);
 
@@ -135,3 +251,38 @@ impl AutoIncrementer {
         lower_bound..upper_bound
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{CallbackStore, ListItemStore};
+    use crate::{List, ListItem, Preview};
+
+    #[test]
+    fn previewing_items_of_dropped_queries_finds_nothing() {
+        let item =
+            || ListItem::new("item").on_preview(|| async { Ok(Preview::Markdown(String::new())) });
+        let mut store = ListItemStore::new();
+        let old = store.store_query_result(List::new(vec![item()])).items[0].id;
+        let new = store.store_query_result(List::new(vec![item()])).items[0].id;
+
+        // activating an item of the newer query drops the older query
+        assert!(store.fetch_callbacks_of(new).is_some());
+        assert!(store.fetch_preview_of(old).is_none());
+        assert!(store.fetch_preview_of(new).is_some());
+    }
+
+    #[test]
+    fn oldest_callbacks_are_dropped() {
+        let mut store = CallbackStore::new();
+        let ids: Vec<u64> = (0..=CallbackStore::<usize>::MAX_CALLBACKS)
+            .map(|i| store.store(i))
+            .collect();
+        let last = CallbackStore::<usize>::MAX_CALLBACKS;
+
+        assert_eq!(store.get(ids[0]), None);
+        assert_eq!(store.get(ids[1]), Some(1));
+        assert_eq!(store.take(ids[1]), Some(1));
+        assert_eq!(store.take(ids[1]), None, "callbacks are only taken once");
+        assert_eq!(store.get(ids[last]), Some(last));
+    }
+}
@@ -6,7 +6,12 @@
 
 use std::path::PathBuf;
 
-use covey_config::{config::GlobalConfig, keyed_list::Id};
+use covey_config::{
+    config::GlobalConfig,
+    hotkey::{HotkeySequence, KeyPress},
+    keyed_list::Id,
+    manifest::Command,
+};
 use serde::{Deserialize, Serialize};
 
 /// This must have an equivalent type on the frontend
@@ -20,14 +25,59 @@ pub enum Event {
     },
     SetList {
         items: Vec<ListItem>,
+        /// Headers to show above groups of items, in order of `start`.
+        sections: Vec<Section>,
         style: Option<ListStyle>,
         plugin_id: Id,
     },
+    /// Asks the user to confirm an activation.
+    ///
+    /// The frontend should respond by calling `confirm` with the same ID.
+    Confirm {
+        // String for the same reason as `ListItemId::local_id`
+        id: String,
+        message: String,
+        plugin_id: Id,
+    },
+    /// Asks the user to enter the parameters of a command.
+    ///
+    /// The frontend should respond by calling `submit_parameters` with the
+    /// same ID.
+    CollectParameters {
+        // String for the same reason as `ListItemId::local_id`
+        id: String,
+        command: Command,
+        plugin_id: Id,
+    },
+    /// Sets the page that is open, or [`None`] to show the main list.
+    SetPage {
+        page: Option<Page>,
+    },
     Reload {
         config: GlobalConfig,
     },
 }
 
+/// A header shown above a group of list items.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "build", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct Section {
+    pub title: String,
+    /// Index of the first item in this section.
+    pub start: u32,
+}
+
+/// A page opened by a plugin on top of the main list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "build", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct Page {
+    pub title: Option<String>,
+    pub placeholder: Option<String>,
+    pub plugin_id: Id,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "build", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
@@ -36,7 +86,83 @@ pub struct ListItem {
     pub description: String,
     pub icon: Option<Icon>,
     pub id: ListItemId,
-    pub available_commands: Vec<String>,
+    /// Every command that can be activated on this item, in order.
+    pub commands: Vec<ItemCommand>,
+    /// Whether the `preview` command will return a preview.
+    pub has_preview: bool,
+    /// Ranges of the title to highlight, in order.
+    pub title_highlights: Vec<Highlight>,
+    /// Ranges of the description to highlight, in order.
+    pub description_highlights: Vec<Highlight>,
+    /// Small extra information to show next to the item, in order.
+    pub accessories: Vec<Accessory>,
+}
+
+/// A command of a list item, which is activated with its `id`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "build", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct ItemCommand {
+    pub id: String,
+    pub title: String,
+    pub description: Option<String>,
+    /// The user's hotkey for this command, or the default hotkey.
+    pub hotkey: Option<HotkeySequence>,
+}
+
+/// What pressing a key with `press_hotkey` did.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "build", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum HotkeyPress {
+    /// A command was activated.
+    Matched,
+    /// The keys pressed so far start a hotkey sequence, but haven't
+    /// finished it yet.
+    ///
+    /// The rest of the sequence needs to be pressed within `timeout_ms`.
+    Pending {
+        pressed: Vec<KeyPress>,
+        timeout_ms: u32,
+    },
+    /// The key isn't part of a hotkey sequence.
+    NoMatch,
+}
+
+/// Small extra information shown next to a list item.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "build", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum Accessory {
+    Text { text: String },
+    IconText { icon: Icon, text: String },
+    Tag { text: String, color: TagColor },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "build", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub enum TagColor {
+    Default,
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+    Grey,
+}
+
+/// A range of characters to highlight.
+///
+/// Indices count unicode code points, not UTF-16 code units.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "build", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct Highlight {
+    pub start: u32,
+    /// Exclusive.
+    pub end: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -53,8 +179,36 @@ pub struct ListItemId {
 #[cfg_attr(feature = "build", derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase", tag = "kind")]
 pub enum Icon {
-    File { path: PathBuf },
-    Text { text: String },
+    File {
+        path: PathBuf,
+    },
+    Text {
+        text: String,
+    },
+    /// An inline image, as a `data:` URI that can be used as an image source.
+    DataUri {
+        uri: String,
+    },
+}
+
+/// A larger preview of a list item.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "build", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum Preview {
+    Markdown {
+        text: String,
+    },
+    Code {
+        code: String,
+        language: Option<String>,
+    },
+    Image {
+        path: PathBuf,
+    },
+    Metadata {
+        entries: Vec<(String, String)>,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,6 +226,9 @@ pub fn export_ts_to(path: impl AsRef<std::path::Path>) {
     let path = path.as_ref();
 
     covey_config::config::GlobalConfig::export_all_to(&path).unwrap();
+    covey_config::conflicts::HotkeyConflict::export_all_to(&path).unwrap();
     covey_config::manifest::PluginManifest::export_all_to(&path).unwrap();
     crate::Event::export_all_to(&path).unwrap();
+    crate::HotkeyPress::export_all_to(&path).unwrap();
+    crate::Preview::export_all_to(&path).unwrap();
 }
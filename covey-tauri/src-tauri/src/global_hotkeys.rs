@@ -0,0 +1,588 @@
+//! System-wide hotkeys that work while the menu is hidden.
+//!
+//! These are registered with [`tauri_plugin_global_shortcut`] on most
+//! platforms. That only works with X11 on linux, so Wayland sessions use
+//! the global shortcuts portal instead. If there is no portal, global
+//! hotkeys are disabled and the user is told to bind `covey --toggle` or
+//! `covey --plugin <id>` in their desktop's settings.
+
+use covey::Input;
+use covey_config::{
+    config::GlobalConfig,
+    hotkey::{Hotkey, KeyCode},
+    keyed_list::Id,
+};
+use parking_lot::Mutex;
+use tauri::Manager;
+use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
+use tauri_plugin_notification::NotificationExt;
+use tokio::sync::oneshot;
+
+use crate::{state::AppState, window};
+
+/// Something that a global hotkey does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// Show or hide the menu.
+    Toggle,
+    /// Show the menu with a plugin's prefix typed in.
+    OpenPlugin(Id),
+}
+
+impl Target {
+    pub fn run(&self, app: &tauri::AppHandle) {
+        match self {
+            Self::Toggle => window::toggle_menu(app),
+            Self::OpenPlugin(id) => {
+                let host = app.state::<AppState>().host().clone();
+                let Some(plugin) = host.plugins().get(id.as_str()).cloned() else {
+                    tracing::warn!("can't open unknown plugin {id:?}");
+                    return;
+                };
+
+                window::show_menu(app);
+                tokio::spawn(host.set_input(Input::new(plugin.prefix().to_owned())));
+            }
+        }
+    }
+
+    /// Parses the target from command line arguments, including the
+    /// program name.
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        let mut args = args.iter().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--toggle" => return Some(Self::Toggle),
+                "--plugin" => return args.next().map(|id| Self::OpenPlugin(Id::new(id))),
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Stable ID to bind this with in the global shortcuts portal.
+    fn portal_id(&self) -> String {
+        match self {
+            Self::Toggle => "toggle".to_owned(),
+            Self::OpenPlugin(id) => format!("open-plugin:{}", id.as_str()),
+        }
+    }
+
+    fn from_portal_id(id: &str) -> Option<Self> {
+        match id {
+            "toggle" => Some(Self::Toggle),
+            _ => id
+                .strip_prefix("open-plugin:")
+                .map(|id| Self::OpenPlugin(Id::new(id))),
+        }
+    }
+
+    fn description(&self) -> String {
+        match self {
+            Self::Toggle => "Show or hide covey".to_owned(),
+            Self::OpenPlugin(id) => format!("Open covey with the {} plugin", id.as_str()),
+        }
+    }
+}
+
+/// The target in covey's own command line arguments, which runs once the
+/// host is set up.
+///
+/// Later instances pass their arguments to the first one instead, which
+/// runs their target straight away.
+pub struct StartupTarget(Mutex<Option<Target>>);
+
+impl StartupTarget {
+    pub fn from_env() -> Self {
+        let args: Vec<_> = std::env::args().collect();
+        Self(Mutex::new(Target::from_args(&args)))
+    }
+
+    /// Runs the target, if it hasn't run yet.
+    pub fn run(&self, app: &tauri::AppHandle) {
+        match self.0.lock().take() {
+            // the menu is already shown when covey starts
+            Some(Target::Toggle) | None => {}
+            Some(target) => target.run(app),
+        }
+    }
+}
+
+/// How global hotkeys are registered on this system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// [`tauri_plugin_global_shortcut`].
+    Plugin,
+    /// The `org.freedesktop.portal.GlobalShortcuts` portal.
+    Portal,
+}
+
+impl Backend {
+    /// The plugin can only grab keys on X11, and fails to start without
+    /// an X server, so Wayland sessions must use the portal.
+    pub fn detect() -> Self {
+        let wayland = cfg!(target_os = "linux")
+            && (std::env::var_os("WAYLAND_DISPLAY").is_some()
+                || std::env::var("XDG_SESSION_TYPE").is_ok_and(|ty| ty == "wayland"));
+
+        if wayland {
+            Self::Portal
+        } else {
+            Self::Plugin
+        }
+    }
+}
+
+/// Must be managed by the app before [`register`] is called.
+pub struct GlobalHotkeys {
+    backend: Backend,
+    /// Closes the current portal session, if there is one.
+    close_portal: Mutex<Option<oneshot::Sender<()>>>,
+}
+
+impl GlobalHotkeys {
+    pub fn new(backend: Backend) -> Self {
+        Self {
+            backend,
+            close_portal: Mutex::new(None),
+        }
+    }
+}
+
+/// Registers the global hotkeys in the config, replacing any that were
+/// registered before.
+///
+/// Hotkeys that can't be registered are reported to the user.
+pub fn register(app: &tauri::AppHandle, config: &GlobalConfig) {
+    let (hotkeys, mut conflicts) = configured(config);
+    let state = app.state::<GlobalHotkeys>();
+
+    match state.backend {
+        Backend::Plugin => {
+            let global_shortcut = app.global_shortcut();
+            if let Err(e) = global_shortcut.unregister_all() {
+                tracing::warn!("failed to unregister global hotkeys: {e}");
+            }
+
+            for (hotkey, target) in hotkeys {
+                let Some(shortcut) = shortcut(&hotkey) else {
+                    conflicts.push(format!(
+                        "{hotkey}: only keys on a US QWERTY keyboard can be global hotkeys"
+                    ));
+                    continue;
+                };
+                let result = global_shortcut.on_shortcut(shortcut, move |app, _shortcut, event| {
+                    if event.state == ShortcutState::Pressed {
+                        target.run(app);
+                    }
+                });
+                if let Err(e) = result {
+                    conflicts.push(format!("{hotkey}: {e}"));
+                }
+            }
+        }
+        Backend::Portal => {
+            let (close, closed) = oneshot::channel();
+            if let Some(previous) = state.close_portal.lock().replace(close) {
+                // the previous session may have already ended
+                _ = previous.send(());
+            }
+            if !hotkeys.is_empty() {
+                tokio::spawn(portal::listen(app.clone(), hotkeys, closed));
+            }
+        }
+    }
+
+    report_conflicts(app, &conflicts);
+}
+
+/// Global hotkeys in the config, with what they do.
+///
+/// If several targets have the same hotkey, only the first one is
+/// returned, and the rest are returned as conflicts.
+fn configured(config: &GlobalConfig) -> (Vec<(Hotkey, Target)>, Vec<String>) {
+    let targets = config
+        .app
+        .toggle_hotkey
+        .iter()
+        .map(|hotkey| (hotkey.clone(), Target::Toggle))
+        .chain(config.plugins.iter().filter_map(|plugin| {
+            let hotkey = plugin.open_hotkey.clone()?;
+            Some((hotkey, Target::OpenPlugin(plugin.id.clone())))
+        }));
+
+    let mut hotkeys: Vec<(Hotkey, Target)> = Vec::new();
+    let mut conflicts = Vec::new();
+    for (hotkey, target) in targets {
+        if let Some((_, existing)) = hotkeys.iter().find(|(other, _)| *other == hotkey) {
+            conflicts.push(format!(
+                "{hotkey} is used to both {} and {}",
+                existing.description().to_lowercase(),
+                target.description().to_lowercase(),
+            ));
+        } else {
+            hotkeys.push((hotkey, target));
+        }
+    }
+
+    (hotkeys, conflicts)
+}
+
+fn report_conflicts(app: &tauri::AppHandle, conflicts: &[String]) {
+    if conflicts.is_empty() {
+        return;
+    }
+
+    for conflict in conflicts {
+        tracing::warn!("global hotkey conflict: {conflict}");
+    }
+
+    let result = app
+        .notification()
+        .builder()
+        .title("Some global hotkeys could not be set")
+        .body(conflicts.join("\n"))
+        .show();
+    if let Err(e) = result {
+        tracing::error!("failed to show notification: {e}");
+    }
+}
+
+/// Returns [`None`] for [`KeyCode::Char`] keys, which have no key code.
+///
+/// Shortcuts are matched by key position, whatever the hotkey's mode is.
+fn shortcut(hotkey: &Hotkey) -> Option<Shortcut> {
+    let mut modifiers = Modifiers::empty();
+    modifiers.set(Modifiers::CONTROL, hotkey.ctrl);
+    modifiers.set(Modifiers::ALT, hotkey.alt);
+    modifiers.set(Modifiers::SHIFT, hotkey.shift);
+    modifiers.set(Modifiers::SUPER, hotkey.meta);
+
+    #[rustfmt::skip]
+    let code = match hotkey.key {
+        KeyCode::Digit0 => Code::Digit0, KeyCode::Digit1 => Code::Digit1,
+        KeyCode::Digit2 => Code::Digit2, KeyCode::Digit3 => Code::Digit3,
+        KeyCode::Digit4 => Code::Digit4, KeyCode::Digit5 => Code::Digit5,
+        KeyCode::Digit6 => Code::Digit6, KeyCode::Digit7 => Code::Digit7,
+        KeyCode::Digit8 => Code::Digit8, KeyCode::Digit9 => Code::Digit9,
+
+        KeyCode::A => Code::KeyA, KeyCode::B => Code::KeyB, KeyCode::C => Code::KeyC,
+        KeyCode::D => Code::KeyD, KeyCode::E => Code::KeyE, KeyCode::F => Code::KeyF,
+        KeyCode::G => Code::KeyG, KeyCode::H => Code::KeyH, KeyCode::I => Code::KeyI,
+        KeyCode::J => Code::KeyJ, KeyCode::K => Code::KeyK, KeyCode::L => Code::KeyL,
+        KeyCode::M => Code::KeyM, KeyCode::N => Code::KeyN, KeyCode::O => Code::KeyO,
+        KeyCode::P => Code::KeyP, KeyCode::Q => Code::KeyQ, KeyCode::R => Code::KeyR,
+        KeyCode::S => Code::KeyS, KeyCode::T => Code::KeyT, KeyCode::U => Code::KeyU,
+        KeyCode::V => Code::KeyV, KeyCode::W => Code::KeyW, KeyCode::X => Code::KeyX,
+        KeyCode::Y => Code::KeyY, KeyCode::Z => Code::KeyZ,
+
+        KeyCode::F1 => Code::F1, KeyCode::F2 => Code::F2, KeyCode::F3 => Code::F3,
+        KeyCode::F4 => Code::F4, KeyCode::F5 => Code::F5, KeyCode::F6 => Code::F6,
+        KeyCode::F7 => Code::F7, KeyCode::F8 => Code::F8, KeyCode::F9 => Code::F9,
+        KeyCode::F10 => Code::F10, KeyCode::F11 => Code::F11, KeyCode::F12 => Code::F12,
+        KeyCode::F13 => Code::F13, KeyCode::F14 => Code::F14, KeyCode::F15 => Code::F15,
+        KeyCode::F16 => Code::F16, KeyCode::F17 => Code::F17, KeyCode::F18 => Code::F18,
+        KeyCode::F19 => Code::F19, KeyCode::F20 => Code::F20, KeyCode::F21 => Code::F21,
+        KeyCode::F22 => Code::F22, KeyCode::F23 => Code::F23, KeyCode::F24 => Code::F24,
+
+        KeyCode::Backtick => Code::Backquote,
+        KeyCode::Hyphen => Code::Minus,
+        KeyCode::Equal => Code::Equal,
+        KeyCode::Tab => Code::Tab,
+        KeyCode::LeftBracket => Code::BracketLeft,
+        KeyCode::RightBracket => Code::BracketRight,
+        KeyCode::Backslash => Code::Backslash,
+        KeyCode::Semicolon => Code::Semicolon,
+        KeyCode::Apostrophe => Code::Quote,
+        KeyCode::Enter => Code::Enter,
+        KeyCode::Comma => Code::Comma,
+        KeyCode::Period => Code::Period,
+        KeyCode::Slash => Code::Slash,
+
+        KeyCode::Escape => Code::Escape,
+        KeyCode::Space => Code::Space,
+        KeyCode::Backspace => Code::Backspace,
+        KeyCode::Delete => Code::Delete,
+        KeyCode::Insert => Code::Insert,
+        KeyCode::ArrowUp => Code::ArrowUp,
+        KeyCode::ArrowDown => Code::ArrowDown,
+        KeyCode::ArrowLeft => Code::ArrowLeft,
+        KeyCode::ArrowRight => Code::ArrowRight,
+        KeyCode::Home => Code::Home,
+        KeyCode::End => Code::End,
+        KeyCode::PageUp => Code::PageUp,
+        KeyCode::PageDown => Code::PageDown,
+
+        KeyCode::Numpad0 => Code::Numpad0, KeyCode::Numpad1 => Code::Numpad1,
+        KeyCode::Numpad2 => Code::Numpad2, KeyCode::Numpad3 => Code::Numpad3,
+        KeyCode::Numpad4 => Code::Numpad4, KeyCode::Numpad5 => Code::Numpad5,
+        KeyCode::Numpad6 => Code::Numpad6, KeyCode::Numpad7 => Code::Numpad7,
+        KeyCode::Numpad8 => Code::Numpad8, KeyCode::Numpad9 => Code::Numpad9,
+        KeyCode::NumpadAdd => Code::NumpadAdd,
+        KeyCode::NumpadSubtract => Code::NumpadSubtract,
+        KeyCode::NumpadMultiply => Code::NumpadMultiply,
+        KeyCode::NumpadDivide => Code::NumpadDivide,
+        KeyCode::NumpadDecimal => Code::NumpadDecimal,
+        KeyCode::NumpadEnter => Code::NumpadEnter,
+
+        KeyCode::Char(_) => return None,
+    };
+
+    Some(Shortcut::new(Some(modifiers), code))
+}
+
+#[cfg(target_os = "linux")]
+mod portal {
+    use ashpd::desktop::global_shortcuts::{GlobalShortcuts, NewShortcut};
+    use color_eyre::eyre::Result;
+    use covey_config::hotkey::{Hotkey, KeyCode};
+    use futures::StreamExt as _;
+    use tokio::sync::oneshot;
+
+    use super::{report_conflicts, Target};
+
+    /// Binds the hotkeys with the portal and runs their targets when they
+    /// are pressed, until `closed` receives or is dropped.
+    ///
+    /// The desktop decides which keys are actually bound, and may ask the
+    /// user first. Hotkeys it didn't bind are reported as conflicts.
+    pub(super) async fn listen(
+        app: tauri::AppHandle,
+        hotkeys: Vec<(Hotkey, Target)>,
+        closed: oneshot::Receiver<()>,
+    ) {
+        if let Err(e) = listen_impl(&app, hotkeys, closed).await {
+            tracing::warn!("global shortcuts portal is unavailable: {e:#}");
+            let message = "This desktop doesn't support global hotkeys. Bind \
+                `covey --toggle` or `covey --plugin <id>` in your desktop's settings instead.";
+            report_conflicts(&app, &[message.to_owned()]);
+        }
+    }
+
+    async fn listen_impl(
+        app: &tauri::AppHandle,
+        hotkeys: Vec<(Hotkey, Target)>,
+        mut closed: oneshot::Receiver<()>,
+    ) -> Result<()> {
+        let portal = GlobalShortcuts::new().await?;
+        let session = portal.create_session().await?;
+
+        let shortcuts: Vec<_> = hotkeys
+            .iter()
+            .map(|(hotkey, target)| {
+                NewShortcut::new(target.portal_id(), target.description())
+                    .preferred_trigger(trigger(hotkey).as_str())
+            })
+            .collect();
+        let bound = portal
+            .bind_shortcuts(&session, &shortcuts, None)
+            .await?
+            .response()?;
+
+        let conflicts: Vec<_> = hotkeys
+            .iter()
+            .filter(|(_, target)| {
+                !bound
+                    .shortcuts()
+                    .iter()
+                    .any(|shortcut| shortcut.id() == target.portal_id())
+            })
+            .map(|(hotkey, target)| {
+                let description = target.description();
+                format!("{hotkey}: the desktop didn't bind \"{description}\"")
+            })
+            .collect();
+        report_conflicts(app, &conflicts);
+
+        let mut activated = portal.receive_activated().await?;
+        loop {
+            tokio::select! {
+                Some(activation) = activated.next() => {
+                    if let Some(target) = Target::from_portal_id(activation.shortcut_id()) {
+                        target.run(app);
+                    }
+                }
+                _ = &mut closed => break,
+                else => break,
+            }
+        }
+
+        session.close().await?;
+        Ok(())
+    }
+
+    /// Formats a hotkey as described by the XDG "shortcuts" specification.
+    pub(super) fn trigger(hotkey: &Hotkey) -> String {
+        let mut trigger = String::new();
+        for (held, modifier) in [
+            (hotkey.ctrl, "CTRL+"),
+            (hotkey.alt, "ALT+"),
+            (hotkey.shift, "SHIFT+"),
+            (hotkey.meta, "LOGO+"),
+        ] {
+            if held {
+                trigger.push_str(modifier);
+            }
+        }
+
+        // xkb keysym names
+        let key = match hotkey.key {
+            KeyCode::Backtick => "grave".to_owned(),
+            KeyCode::Hyphen => "minus".to_owned(),
+            KeyCode::Equal => "equal".to_owned(),
+            KeyCode::Tab => "Tab".to_owned(),
+            KeyCode::LeftBracket => "bracketleft".to_owned(),
+            KeyCode::RightBracket => "bracketright".to_owned(),
+            KeyCode::Backslash => "backslash".to_owned(),
+            KeyCode::Semicolon => "semicolon".to_owned(),
+            KeyCode::Apostrophe => "apostrophe".to_owned(),
+            KeyCode::Enter => "Return".to_owned(),
+            KeyCode::Comma => "comma".to_owned(),
+            KeyCode::Period => "period".to_owned(),
+            KeyCode::Slash => "slash".to_owned(),
+            KeyCode::Escape => "Escape".to_owned(),
+            KeyCode::Space => "space".to_owned(),
+            KeyCode::Backspace => "BackSpace".to_owned(),
+            KeyCode::Delete => "Delete".to_owned(),
+            KeyCode::Insert => "Insert".to_owned(),
+            KeyCode::ArrowUp => "Up".to_owned(),
+            KeyCode::ArrowDown => "Down".to_owned(),
+            KeyCode::ArrowLeft => "Left".to_owned(),
+            KeyCode::ArrowRight => "Right".to_owned(),
+            KeyCode::Home => "Home".to_owned(),
+            KeyCode::End => "End".to_owned(),
+            KeyCode::PageUp => "Page_Up".to_owned(),
+            KeyCode::PageDown => "Page_Down".to_owned(),
+            KeyCode::NumpadAdd => "KP_Add".to_owned(),
+            KeyCode::NumpadSubtract => "KP_Subtract".to_owned(),
+            KeyCode::NumpadMultiply => "KP_Multiply".to_owned(),
+            KeyCode::NumpadDivide => "KP_Divide".to_owned(),
+            KeyCode::NumpadDecimal => "KP_Decimal".to_owned(),
+            KeyCode::NumpadEnter => "KP_Enter".to_owned(),
+            // keysyms of other characters are their unicode code point
+            KeyCode::Char(c) => format!("U{:04X}", u32::from(c)),
+            // "Num0" is "KP_0"
+            ref numpad @ (KeyCode::Numpad0
+            | KeyCode::Numpad1
+            | KeyCode::Numpad2
+            | KeyCode::Numpad3
+            | KeyCode::Numpad4
+            | KeyCode::Numpad5
+            | KeyCode::Numpad6
+            | KeyCode::Numpad7
+            | KeyCode::Numpad8
+            | KeyCode::Numpad9) => numpad.to_string().replace("Num", "KP_"),
+            // letters are lowercase, digits and function keys are the same
+            ref other => {
+                let name = other.to_string();
+                if name.len() == 1 {
+                    name.to_lowercase()
+                } else {
+                    name
+                }
+            }
+        };
+        trigger.push_str(&key);
+        trigger
+    }
+}
+
+/// The portal only exists on linux, where [`Backend::detect`] may choose it.
+#[cfg(not(target_os = "linux"))]
+mod portal {
+    use covey_config::hotkey::Hotkey;
+    use tokio::sync::oneshot;
+
+    use super::Target;
+
+    #[expect(clippy::unused_async, reason = "must match the linux signature")]
+    pub(super) async fn listen(
+        _app: tauri::AppHandle,
+        _hotkeys: Vec<(Hotkey, Target)>,
+        _closed: oneshot::Receiver<()>,
+    ) {
+        unreachable!("the portal backend is only used on linux")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use covey_config::{config::GlobalConfig, keyed_list::Id};
+
+    use super::{configured, Target};
+
+    fn args(args: &[&str]) -> Option<Target> {
+        let args: Vec<_> = ["covey"]
+            .iter()
+            .chain(args)
+            .map(|arg| (*arg).to_owned())
+            .collect();
+        Target::from_args(&args)
+    }
+
+    #[test]
+    fn targets_parse_from_args() {
+        assert_eq!(args(&[]), None);
+        assert_eq!(args(&["--toggle"]), Some(Target::Toggle));
+        assert_eq!(
+            args(&["--verbose", "--plugin", "files"]),
+            Some(Target::OpenPlugin(Id::new("files")))
+        );
+        assert_eq!(args(&["--plugin"]), None);
+        // the program name is skipped
+        assert_eq!(Target::from_args(&["--toggle".to_owned()]), None);
+    }
+
+    #[test]
+    fn targets_round_trip_through_portal_ids() {
+        for target in [Target::Toggle, Target::OpenPlugin(Id::new("files"))] {
+            assert_eq!(Target::from_portal_id(&target.portal_id()), Some(target));
+        }
+        assert_eq!(Target::from_portal_id("unknown"), None);
+    }
+
+    #[test]
+    fn duplicate_global_hotkeys_conflict() {
+        let config: GlobalConfig = serde_json::from_value(serde_json::json!({
+            "app": { "toggle-hotkey": { "key": "space", "alt": true } },
+            "plugins": [
+                {
+                    "id": "files",
+                    "prefix": "f",
+                    "open-hotkey": { "key": "space", "alt": true },
+                },
+                {
+                    "id": "apps",
+                    "prefix": "a",
+                    "open-hotkey": { "key": "a", "meta": true },
+                },
+                { "id": "none", "prefix": "n" },
+            ],
+        }))
+        .unwrap();
+
+        let (hotkeys, conflicts) = configured(&config);
+        let targets: Vec<_> = hotkeys.into_iter().map(|(_, target)| target).collect();
+        assert_eq!(
+            targets,
+            [Target::Toggle, Target::OpenPlugin(Id::new("apps"))]
+        );
+        assert_eq!(
+            conflicts,
+            ["Alt+Space is used to both show or hide covey and open covey with the files plugin"]
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn portal_triggers_use_keysym_names() {
+        let trigger = |s: &str| super::portal::trigger(&s.parse().unwrap());
+        assert_eq!(trigger("ctrl+alt+t"), "CTRL+ALT+t");
+        assert_eq!(trigger("meta+shift+enter"), "SHIFT+LOGO+Return");
+        assert_eq!(trigger("alt+1"), "ALT+1");
+        assert_eq!(trigger("alt+f12"), "ALT+F12");
+        assert_eq!(trigger("ctrl+num5"), "CTRL+KP_5");
+        assert_eq!(trigger("ctrl+pageup"), "CTRL+Page_Up");
+        assert_eq!(trigger("alt+é"), "ALT+U00E9");
+    }
+}
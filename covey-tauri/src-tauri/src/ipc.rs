@@ -1,9 +1,18 @@
 use color_eyre::eyre::Result;
-use covey_config::{config::GlobalConfig, keyed_list::Id, manifest::PluginManifest};
-use covey_tauri_types::{Event, ListItemId};
+use covey_config::{
+    config::GlobalConfig,
+    conflicts::HotkeyConflict,
+    hotkey::{KeyPress, SequenceMatch, SequenceMatcher},
+    keyed_list::Id,
+    manifest::PluginManifest,
+};
+use covey_tauri_types::{Event, HotkeyPress, ListItemId, Preview};
 use tauri::{ipc::Channel, Manager, State, WebviewWindowBuilder};
 
-use crate::state::{AppState, EventChannel};
+use crate::{
+    global_hotkeys::{self, StartupTarget},
+    state::{AppState, EventChannel},
+};
 
 #[tauri::command]
 pub fn setup(
@@ -19,8 +28,13 @@ fn setup_impl(
     state: State<'_, AppState>,
     channel: Channel<Event>,
 ) -> Result<()> {
-    let frontend = EventChannel { channel, app };
+    let frontend = EventChannel {
+        channel,
+        app: app.clone(),
+    };
     state.init(frontend)?;
+    global_hotkeys::register(&app, &state.host().config());
+    app.state::<StartupTarget>().run(&app);
     Ok(())
 }
 
@@ -42,6 +56,145 @@ pub fn activate(state: State<'_, AppState>, list_item_id: ListItemId, command_na
     }
 }
 
+/// Presses a key while a list item is selected, or while nothing is
+/// selected if `list_item_id` is `None`.
+///
+/// This activates the item's command with that hotkey sequence, or reloads
+/// the plugin for the reload hotkey.
+#[tauri::command]
+pub fn press_hotkey(
+    state: State<'_, AppState>,
+    list_item_id: Option<ListItemId>,
+    press: KeyPress,
+) -> HotkeyPress {
+    let item = list_item_id.and_then(|id| {
+        let item = state.find_list_item(&id);
+        if item.is_none() {
+            tracing::warn!("list item with id {id:?} not found");
+        }
+        item
+    });
+
+    let host = state.host();
+    match host.press_hotkey(item.as_ref(), press) {
+        SequenceMatch::Matched(fut) => {
+            tokio::spawn(fut);
+            HotkeyPress::Matched
+        }
+        SequenceMatch::Pending => HotkeyPress::Pending {
+            pressed: host.pending_hotkeys(),
+            // the host's matcher has the default timeout
+            timeout_ms: u32::try_from(SequenceMatcher::DEFAULT_TIMEOUT.as_millis())
+                .unwrap_or(u32::MAX),
+        },
+        SequenceMatch::NoMatch => HotkeyPress::NoMatch,
+    }
+}
+
+/// Fetches the preview of a list item.
+///
+/// Returns [`None`] if the list item has no preview.
+#[tauri::command]
+pub async fn preview(
+    state: State<'_, AppState>,
+    list_item_id: ListItemId,
+) -> Result<Option<Preview>, String> {
+    let Some(item) = state.find_list_item(&list_item_id) else {
+        return Err(format!("list item with id {list_item_id:?} not found"));
+    };
+
+    let preview = state
+        .host()
+        .preview(item)
+        .await
+        .map_err(|e| format!("{e:#}"))?;
+
+    Ok(preview.map(|preview| match preview {
+        covey::Preview::Markdown(text) => Preview::Markdown { text },
+        covey::Preview::Code { code, language } => Preview::Code { code, language },
+        covey::Preview::Image(path) => Preview::Image { path },
+        covey::Preview::Metadata(entries) => Preview::Metadata { entries },
+    }))
+}
+
+#[tauri::command]
+pub fn confirm(state: State<'_, AppState>, id: String, accepted: bool) {
+    match id.parse() {
+        Ok(id) => {
+            tokio::spawn(state.host().confirm(id, accepted));
+        }
+        Err(e) => tracing::warn!("invalid confirmation id {id:?}: {e}"),
+    }
+}
+
+/// Submits the parameters of a command, or cancels the command if `values`
+/// is `None`.
+///
+/// Returns an error message if the values are invalid.
+#[tauri::command]
+pub fn submit_parameters(
+    state: State<'_, AppState>,
+    id: String,
+    values: Option<serde_json::Map<String, serde_json::Value>>,
+) -> Result<(), String> {
+    let id = id
+        .parse()
+        .map_err(|e| format!("invalid parameter request id {id:?}: {e}"))?;
+    let fut = state
+        .host()
+        .submit_parameters(id, values)
+        .map_err(|e| e.to_string())?;
+    tokio::spawn(fut);
+    Ok(())
+}
+
+/// Closes the current page.
+///
+/// Returns whether there was a page to close.
+#[tauri::command]
+pub fn pop_page(state: State<'_, AppState>) -> bool {
+    if let Some(fut) = state.host().pop_page() {
+        tokio::spawn(fut);
+        true
+    } else {
+        false
+    }
+}
+
+/// Sets the input to the previous query in the history.
+///
+/// Returns whether there was an older query.
+#[tauri::command]
+pub fn history_previous(state: State<'_, AppState>) -> bool {
+    if let Some(fut) = state.host().history_previous() {
+        tokio::spawn(fut);
+        true
+    } else {
+        false
+    }
+}
+
+/// Sets the input to the next query in the history.
+///
+/// Returns whether the input was from the history.
+#[tauri::command]
+pub fn history_next(state: State<'_, AppState>) -> bool {
+    if let Some(fut) = state.host().history_next() {
+        tokio::spawn(fut);
+        true
+    } else {
+        false
+    }
+}
+
+/// Submitted queries that contain `search`, with the newest first.
+///
+/// These include the plugin's prefix, so they can be set as the input.
+#[tauri::command]
+pub fn search_history(state: State<'_, AppState>, search: String) -> Vec<String> {
+    state.host().search_history(&search)
+}
+
 #[tauri::command]
 pub fn reload_plugin(state: State<'_, AppState>, plugin_id: Id) {
     state.host().reload_plugin(&plugin_id);
@@ -76,11 +229,20 @@ pub fn get_global_config(state: State<'_, AppState>) -> GlobalConfig {
 
 /// Must be called after the app is initialised.
 #[tauri::command]
-pub fn set_global_config(state: State<'_, AppState>, config: GlobalConfig) {
+pub fn set_global_config(app: tauri::AppHandle, state: State<'_, AppState>, config: GlobalConfig) {
     tracing::debug!("received global config {config:#?}");
+    global_hotkeys::register(&app, &config);
     state.host().reload(config)
 }
 
+/// Must be called after the app is initialised.
+///
+/// These are updated by `set_global_config`.
+#[tauri::command]
+pub fn get_hotkey_conflicts(state: State<'_, AppState>) -> Vec<HotkeyConflict> {
+    state.host().hotkey_conflicts()
+}
+
 #[tauri::command]
 pub fn get_manifest(state: State<'_, AppState>, plugin_name: String) -> Option<PluginManifest> {
     state
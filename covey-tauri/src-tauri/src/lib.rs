@@ -1,7 +1,10 @@
+mod global_hotkeys;
 mod ipc;
+mod paste;
 mod state;
 mod window;
 
+use global_hotkeys::{Backend, GlobalHotkeys, StartupTarget, Target};
 use state::AppState;
 use tauri::{
     menu::{Menu, MenuItem},
@@ -11,17 +14,29 @@ use tauri::{
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
+    let hotkey_backend = Backend::detect();
+
+    let mut builder = tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
-        .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
-            window::show_menu(app);
-        }))
+        .plugin(tauri_plugin_single_instance::init(
+            |app, args, _cwd| match Target::from_args(&args) {
+                Some(target) => target.run(app),
+                None => window::show_menu(app),
+            },
+        ))
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_notification::init())
-        .plugin(tauri_plugin_fs::init())
-        .setup(|app| {
+        .plugin(tauri_plugin_fs::init());
+    if hotkey_backend == Backend::Plugin {
+        builder = builder.plugin(tauri_plugin_global_shortcut::Builder::new().build());
+    }
+
+    builder
+        .setup(move |app| {
             app.manage(AppState::new());
+            app.manage(GlobalHotkeys::new(hotkey_backend));
+            app.manage(StartupTarget::from_env());
 
             TrayIconBuilder::new()
                 .icon(app.default_window_icon().unwrap().clone())
@@ -84,9 +99,18 @@ pub fn run() {
             ipc::setup,
             ipc::query,
             ipc::activate,
+            ipc::press_hotkey,
+            ipc::pop_page,
+            ipc::history_previous,
+            ipc::history_next,
+            ipc::search_history,
+            ipc::preview,
+            ipc::confirm,
+            ipc::submit_parameters,
             ipc::show_settings_window,
             ipc::get_global_config,
             ipc::set_global_config,
+            ipc::get_hotkey_conflicts,
             ipc::get_manifest,
             ipc::reload_plugin,
         ])
@@ -0,0 +1,156 @@
+//! Inserting text into the window that was focused before covey was shown.
+//!
+//! Text is inserted by setting the clipboard then simulating a Ctrl+V with
+//! one of `wtype`, `xdotool` or `ydotool`, whichever is available. If the
+//! clipboard can't be set, the text is typed out with the same tool instead.
+//! The previous clipboard contents are restored afterwards, even if pasting
+//! failed.
+//!
+//! Only text can be restored: if the clipboard held something else, such as
+//! an image or copied files, it is lost.
+
+use std::{process::Stdio, sync::LazyLock, time::Duration};
+
+use color_eyre::eyre::{bail, eyre, Context, Result};
+use covey::is_in_path;
+use parking_lot::Mutex;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tokio::process::Command;
+
+/// How long to wait for the previous window to regain focus after
+/// hiding covey.
+const FOCUS_DELAY: Duration = Duration::from_millis(150);
+
+/// How long to wait for the target window to read the clipboard before
+/// restoring the previous contents.
+const RESTORE_DELAY: Duration = Duration::from_millis(300);
+
+/// X11 window ID of the window that was focused before covey was shown.
+static PREVIOUS_WINDOW: Mutex<Option<String>> = Mutex::new(None);
+
+/// Tool used to simulate key presses.
+///
+/// This is detected once, using the first one that is available
+/// for the current session.
+static BACKEND: LazyLock<Option<Backend>> = LazyLock::new(Backend::detect);
+
+#[derive(Debug, Clone, Copy)]
+enum Backend {
+    Wtype,
+    Xdotool,
+    Ydotool,
+}
+
+impl Backend {
+    fn detect() -> Option<Self> {
+        let is_wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+        let is_x11 = std::env::var_os("DISPLAY").is_some();
+
+        let candidates: &[(Self, &str)] = if is_wayland {
+            &[(Self::Wtype, "wtype"), (Self::Ydotool, "ydotool")]
+        } else if is_x11 {
+            &[(Self::Xdotool, "xdotool"), (Self::Ydotool, "ydotool")]
+        } else {
+            &[(Self::Ydotool, "ydotool")]
+        };
+
+        let backend = candidates
+            .iter()
+            .find(|(_, bin)| is_in_path(bin))
+            .map(|(backend, _)| *backend);
+        tracing::info!("using paste backend {backend:?}");
+        backend
+    }
+
+    /// Simulates a Ctrl+V key press.
+    async fn press_paste(self) -> Result<()> {
+        match self {
+            Self::Wtype => run("wtype", &["-M", "ctrl", "v", "-m", "ctrl"]).await,
+            Self::Xdotool => run("xdotool", &["key", "--clearmodifiers", "ctrl+v"]).await,
+            // key codes for left ctrl (29) and v (47)
+            Self::Ydotool => run("ydotool", &["key", "29:1", "47:1", "47:0", "29:0"]).await,
+        }
+    }
+
+    /// Types out the text with a virtual keyboard.
+    async fn type_text(self, text: &str) -> Result<()> {
+        match self {
+            Self::Wtype => run("wtype", &["--", text]).await,
+            Self::Xdotool => run("xdotool", &["type", "--clearmodifiers", "--", text]).await,
+            Self::Ydotool => run("ydotool", &["type", "--", text]).await,
+        }
+    }
+}
+
+/// Remembers the currently focused window so that focus can be returned
+/// to it before pasting.
+///
+/// This should be called before covey is shown. Only does anything on X11:
+/// Wayland compositors return focus to the previous window by themselves.
+pub fn remember_focused_window() {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() || !is_in_path("xdotool") {
+        return;
+    }
+
+    let window = std::process::Command::new("xdotool")
+        .arg("getactivewindow")
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_owned());
+    *PREVIOUS_WINDOW.lock() = window;
+}
+
+/// Inserts text into the previously focused window.
+///
+/// Covey should already be hidden before calling this.
+pub async fn paste(app: &tauri::AppHandle, text: String) -> Result<()> {
+    let Some(backend) = *BACKEND else {
+        app.clipboard().write_text(text)?;
+        bail!("no tool to simulate key presses found: the text has been copied instead");
+    };
+
+    let previous_window = PREVIOUS_WINDOW.lock().take();
+    if let Some(window) = previous_window {
+        // the clipboard hasn't been changed yet, so there is nothing to restore
+        run("xdotool", &["windowactivate", "--sync", &window]).await?;
+    }
+    tokio::time::sleep(FOCUS_DELAY).await;
+
+    // non-text contents can't be read, so they won't be restored
+    let previous_clipboard = app.clipboard().read_text().ok();
+    if app.clipboard().write_text(&text).is_err() {
+        return backend.type_text(&text).await;
+    }
+    let pasted = backend.press_paste().await;
+
+    let restored = match previous_clipboard {
+        Some(previous) => {
+            tokio::time::sleep(RESTORE_DELAY).await;
+            app.clipboard()
+                .write_text(previous)
+                .context("failed to restore clipboard")
+        }
+        None => Ok(()),
+    };
+
+    pasted.and(restored)
+}
+
+async fn run(cmd: &str, args: &[&str]) -> Result<()> {
+    let status = Command::new(cmd)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .context(format!("failed to run `{cmd}`"))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(eyre!("`{cmd}` exited with {status}"))
+    }
+}
@@ -1,14 +1,20 @@
-use std::sync::OnceLock;
+use std::{ops::Range, sync::OnceLock};
 
+use base64::Engine as _;
 use color_eyre::eyre::Result;
-use covey::{Frontend, Host};
+use covey::{icons::ResolvedIcon, Frontend, Host};
+use covey_tauri_types::{
+    Accessory, Highlight, Icon, ItemCommand, ListItemId, Page, Section, TagColor,
+};
 pub use covey_tauri_types::{Event, ListItem, ListStyle};
-use covey_tauri_types::{Icon, ListItemId};
 use tauri::{ipc::Channel, Manager};
 use tauri_plugin_clipboard_manager::ClipboardExt;
 use tauri_plugin_notification::NotificationExt;
 
-use crate::window;
+use crate::{paste, window};
+
+/// Size of list item icons in logical pixels.
+const ICON_SIZE: u32 = 48;
 
 /// Must be initialised exactly once with [`AppState::init`].
 pub struct AppState {
@@ -43,20 +49,53 @@ impl AppState {
         self.inner.get().expect("app state has not been set up")
     }
 
+    /// Converts list items to be sent to the frontend.
+    ///
+    /// Named icons are resolved at `scale`, the scale factor of the
+    /// display the menu is on.
     pub fn register_list_items(
         &self,
         lis: impl ExactSizeIterator<Item = covey::ListItem>,
+        scale: u32,
     ) -> Vec<ListItem> {
+        let icons = self.host().icons();
+
+        let resolve_icon = |icon: covey::Icon| match icon {
+            covey::Icon::Name(name) => {
+                icons
+                    .resolve(&name, ICON_SIZE, scale)
+                    .map(|resolved| match resolved {
+                        ResolvedIcon::File(path) => Icon::File { path },
+                        ResolvedIcon::Svg(svg) => Icon::DataUri {
+                            uri: data_uri("image/svg+xml", svg.as_bytes()),
+                        },
+                    })
+            }
+            covey::Icon::Text(text) => Some(Icon::Text { text }),
+            covey::Icon::File(path) => Some(Icon::File { path }),
+            covey::Icon::Image { data, mime_type } => Some(Icon::DataUri {
+                uri: data_uri(&mime_type, &data),
+            }),
+        };
+
         lis.map(|li| {
-            let icon: Option<Icon> = match li.icon() {
-                Some(covey::Icon::Name(name)) => freedesktop_icons::lookup(&name)
-                    .with_cache()
-                    .with_size(48)
-                    .find()
-                    .map(|path| Icon::File { path }),
-                Some(covey::Icon::Text(text)) => Some(Icon::Text { text }),
-                None => None,
-            };
+            let icon = li.icon().and_then(resolve_icon);
+            let accessories = li
+                .accessories()
+                .iter()
+                .cloned()
+                .map(|accessory| match accessory {
+                    covey::Accessory::Text(text) => Accessory::Text { text },
+                    covey::Accessory::IconText { icon, text } => match resolve_icon(icon) {
+                        Some(icon) => Accessory::IconText { icon, text },
+                        None => Accessory::Text { text },
+                    },
+                    covey::Accessory::Tag { text, color } => Accessory::Tag {
+                        text,
+                        color: tag_color_from_covey(color),
+                    },
+                })
+                .collect();
             let id = ListItemId {
                 local_id: li.id().local_id.to_string(),
                 plugin_id: li.id().plugin.id().to_owned(),
@@ -67,7 +106,20 @@ impl AppState {
                 description: li.description().to_owned(),
                 icon,
                 id,
-                available_commands: li.available_commands().to_vec(),
+                commands: li
+                    .commands()
+                    .into_iter()
+                    .map(|command| ItemCommand {
+                        id: command.id,
+                        title: command.title,
+                        description: command.description,
+                        hotkey: command.hotkey,
+                    })
+                    .collect(),
+                has_preview: li.has_preview(),
+                title_highlights: highlights(li.title_highlights()),
+                description_highlights: highlights(li.description_highlights()),
+                accessories,
             }
         })
         .collect()
@@ -96,6 +148,26 @@ impl covey::Frontend for EventChannel {
         self.app.clipboard().write_text(str).unwrap();
     }
 
+    fn paste(&mut self, str: String) {
+        window::hide_menu(&self.app);
+
+        let app = self.app.clone();
+        tokio::spawn(async move {
+            if let Err(e) = paste::paste(&app, str).await {
+                tracing::error!("failed to paste: {e:#}");
+                let shown = app
+                    .notification()
+                    .builder()
+                    .title("Failed to paste")
+                    .body(format!("{e:#}"))
+                    .show();
+                if let Err(e) = shown {
+                    tracing::error!("failed to show notification: {e}");
+                }
+            }
+        });
+    }
+
     fn set_input(&mut self, input: covey::Input) {
         self.channel
             .send(Event::SetInput {
@@ -107,15 +179,74 @@ impl covey::Frontend for EventChannel {
 
     fn set_list(&mut self, list: covey::List) {
         let state = self.app.state::<AppState>();
+        let scale = menu_scale(&self.app);
         self.channel
             .send(Event::SetList {
-                items: state.register_list_items(list.items.into_iter()),
+                items: state.register_list_items(list.items.into_iter(), scale),
+                sections: list
+                    .sections
+                    .into_iter()
+                    .map(|section| Section {
+                        title: section.title,
+                        start: u32::try_from(section.start).unwrap_or(u32::MAX),
+                    })
+                    .collect(),
                 style: list.style.map(list_style_from_covey),
                 plugin_id: list.plugin.id().clone(),
             })
             .unwrap();
     }
 
+    fn notify(&mut self, notification: covey::Notification) {
+        // the notification plugin has no urgency setting on desktop,
+        // so all urgencies are shown the same way.
+        let mut builder = self
+            .app
+            .notification()
+            .builder()
+            .title(notification.title)
+            .body(notification.body);
+        if let Some(icon) = notification.icon {
+            builder = builder.icon(icon);
+        }
+
+        if let Err(e) = builder.show() {
+            tracing::error!("failed to show notification: {e}");
+        }
+    }
+
+    fn confirm(&mut self, confirmation: covey::Confirmation) {
+        self.channel
+            .send(Event::Confirm {
+                id: confirmation.id.to_string(),
+                message: confirmation.message,
+                plugin_id: confirmation.plugin.id().clone(),
+            })
+            .unwrap();
+    }
+
+    fn collect_parameters(&mut self, request: covey::ParameterRequest) {
+        self.channel
+            .send(Event::CollectParameters {
+                id: request.id.to_string(),
+                command: request.command,
+                plugin_id: request.plugin.id().clone(),
+            })
+            .unwrap();
+    }
+
+    fn set_page(&mut self, page: Option<covey::Page>) {
+        self.channel
+            .send(Event::SetPage {
+                page: page.map(|page| Page {
+                    title: page.title,
+                    placeholder: page.placeholder,
+                    plugin_id: page.plugin.id().clone(),
+                }),
+            })
+            .unwrap();
+    }
+
     fn reload(&mut self, config: covey_config::config::GlobalConfig) {
         tracing::info!("reloading at the front end");
         self.channel.send(Event::Reload { config }).unwrap();
@@ -132,6 +263,25 @@ impl covey::Frontend for EventChannel {
     }
 }
 
+/// Scale factor of the display the menu is on, rounded up.
+#[expect(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "scale factors are small positive numbers"
+)]
+fn menu_scale(app: &tauri::AppHandle) -> u32 {
+    app.get_webview_window("main")
+        .and_then(|window| window.scale_factor().ok())
+        .map_or(1, |scale| scale.ceil().max(1.0) as u32)
+}
+
+fn data_uri(mime_type: &str, data: &[u8]) -> String {
+    format!(
+        "data:{mime_type};base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(data)
+    )
+}
+
 fn list_style_from_covey(value: covey::ListStyle) -> ListStyle {
     match value {
         covey::ListStyle::Rows => ListStyle::Rows,
@@ -139,3 +289,26 @@ fn list_style_from_covey(value: covey::ListStyle) -> ListStyle {
         covey::ListStyle::GridWithColumns(columns) => ListStyle::GridWithColumns { columns },
     }
 }
+
+fn highlights(ranges: Vec<Range<usize>>) -> Vec<Highlight> {
+    ranges
+        .into_iter()
+        .map(|range| Highlight {
+            start: u32::try_from(range.start).unwrap_or(u32::MAX),
+            end: u32::try_from(range.end).unwrap_or(u32::MAX),
+        })
+        .collect()
+}
+
+fn tag_color_from_covey(value: covey::TagColor) -> TagColor {
+    match value {
+        covey::TagColor::Default => TagColor::Default,
+        covey::TagColor::Red => TagColor::Red,
+        covey::TagColor::Orange => TagColor::Orange,
+        covey::TagColor::Yellow => TagColor::Yellow,
+        covey::TagColor::Green => TagColor::Green,
+        covey::TagColor::Blue => TagColor::Blue,
+        covey::TagColor::Purple => TagColor::Purple,
+        covey::TagColor::Grey => TagColor::Grey,
+    }
+}
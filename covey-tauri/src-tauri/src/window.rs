@@ -5,7 +5,9 @@ use tauri::Manager;
 pub fn hide_menu(app: &tauri::AppHandle) {
     eprintln!("hiding window");
     if let Some(window) = app.get_webview_window("main") {
-        window.hide().unwrap();
+        if let Err(e) = window.hide() {
+            tracing::error!("failed to hide window: {e}");
+        }
     } else {
         eprintln!("WARN: main window was not found");
     }
@@ -14,13 +16,33 @@ pub fn hide_menu(app: &tauri::AppHandle) {
 pub fn show_menu(app: &tauri::AppHandle) {
     eprintln!("showing window");
     if let Some(window) = app.get_webview_window("main") {
-        window.show().unwrap();
-        window.set_focus().unwrap();
+        if !window.is_visible().unwrap_or(false) {
+            crate::paste::remember_focused_window();
+        }
         // maximise in case the target monitor changes.
-        window.set_resizable(true).unwrap();
-        window.maximize().unwrap();
-        window.set_resizable(false).unwrap();
+        let shown = window
+            .show()
+            .and_then(|()| window.set_focus())
+            .and_then(|()| window.set_resizable(true))
+            .and_then(|()| window.maximize())
+            .and_then(|()| window.set_resizable(false));
+        if let Err(e) = shown {
+            tracing::error!("failed to show window: {e}");
+        }
     } else {
         eprintln!("WARN: main window was not found");
     }
 }
+
+/// Hides the menu if it is visible, otherwise shows it.
+pub fn toggle_menu(app: &tauri::AppHandle) {
+    let visible = app
+        .get_webview_window("main")
+        .is_some_and(|window| window.is_visible().unwrap_or(false));
+
+    if visible {
+        hide_menu(app);
+    } else {
+        show_menu(app);
+    }
+}
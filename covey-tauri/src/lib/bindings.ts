@@ -2,17 +2,29 @@
  * Re-exported bindings with some overridden to make records required.
  */
 
+import type { Accessory } from "./bindings/Accessory";
 import type { Command } from "./bindings/Command";
 import type { Event } from "./bindings/Event";
 import type { GlobalConfig as GlobalConfigBinding } from "./bindings/GlobalConfig";
+import type { Highlight } from "./bindings/Highlight";
 import type { Hotkey } from "./bindings/Hotkey";
+import type { HotkeyBinding } from "./bindings/HotkeyBinding";
+import type { HotkeyConflict } from "./bindings/HotkeyConflict";
+import type { HotkeyPress } from "./bindings/HotkeyPress";
+import type { HotkeySequence } from "./bindings/HotkeySequence";
+import type { HotkeySource } from "./bindings/HotkeySource";
 import type { Icon } from "./bindings/Icon";
 import type { Id } from "./bindings/Id";
+import type { ItemCommand } from "./bindings/ItemCommand";
 import type { KeyCode } from "./bindings/KeyCode";
 import type { KeyedList } from "./bindings/KeyedList";
+import type { KeyMode } from "./bindings/KeyMode";
+import type { KeyPress } from "./bindings/KeyPress";
 import type { ListItem } from "./bindings/ListItem";
 import type { ListItemId } from "./bindings/ListItemId";
 import type { ListStyle } from "./bindings/ListStyle";
+import type { Page } from "./bindings/Page";
+import type { Preview } from "./bindings/Preview";
 import type { PluginConfig as PluginConfigBinding } from "./bindings/PluginConfig";
 import type { PluginConfigSchema as PluginConfigSchemaBinding } from "./bindings/PluginConfigSchema";
 import type { PluginManifest as PluginManifestBinding } from "./bindings/PluginManifest";
@@ -26,24 +38,38 @@ import type { SchemaSelection } from "./bindings/SchemaSelection";
 import type { SchemaStruct as SchemaStructBinding } from "./bindings/SchemaStruct";
 import type { SchemaText } from "./bindings/SchemaText";
 import type { SchemaType as SchemaTypeBinding } from "./bindings/SchemaType";
+import type { Section } from "./bindings/Section";
+import type { TagColor } from "./bindings/TagColor";
 import type { JsonValue as JsonValueBinding } from "./bindings/serde_json/JsonValue";
 
 export type {
+  Accessory,
   Command,
   Event,
   GlobalConfig,
+  Highlight,
   Hotkey,
+  HotkeyBinding,
+  HotkeyConflict,
+  HotkeyPress,
+  HotkeySequence,
+  HotkeySource,
   Icon,
   Id,
+  ItemCommand,
   JsonValue,
   KeyCode,
   KeyedList,
+  KeyMode,
+  KeyPress,
   ListItem,
   ListItemId,
   ListStyle,
+  Page,
   PluginConfig,
   PluginConfigSchema,
   PluginManifest,
+  Preview,
   SchemaBool,
   SchemaFilePath,
   SchemaFolderPath,
@@ -54,6 +80,8 @@ export type {
   SchemaStruct,
   SchemaText,
   SchemaType,
+  Section,
+  TagColor,
 };
 
 type JsonValue =
@@ -90,7 +118,7 @@ type GlobalConfig = Omit<GlobalConfigBinding, "plugins"> & {
 
 type PluginConfig = PluginConfigBinding & {
   config: Record<string, JsonValue>;
-  commands: Record<Id, Hotkey>;
+  commands: Record<Id, HotkeySequence>;
 };
 
 type PluginManifest = PluginManifestBinding & {
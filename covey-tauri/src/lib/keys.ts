@@ -1,4 +1,4 @@
-import type { Hotkey, KeyCode } from "./bindings";
+import type { Hotkey, KeyCode, KeyMode, KeyPress } from "./bindings";
 import type { DeepReadonly } from "./utils";
 
 export const symbolToKeyCode = (symbol: string): KeyCode | undefined => {
@@ -67,6 +67,132 @@ export const symbolToKeyCode = (symbol: string): KeyCode | undefined => {
     case "/":
     case "?":
       return "slash";
+    case "escape":
+      return "escape";
+    case " ":
+      return "space";
+    case "backspace":
+      return "backspace";
+    case "delete":
+      return "delete";
+    case "insert":
+      return "insert";
+    case "arrowup":
+      return "arrow-up";
+    case "arrowdown":
+      return "arrow-down";
+    case "arrowleft":
+      return "arrow-left";
+    case "arrowright":
+      return "arrow-right";
+    case "home":
+      return "home";
+    case "end":
+      return "end";
+    case "pageup":
+      return "page-up";
+    case "pagedown":
+      return "page-down";
+    default:
+      return otherCharToKeyCode(key);
+  }
+};
+
+/**
+ * Converts a character that isn't on a US QWERTY keyboard, like "é".
+ *
+ * This must match `KeyCode::Char` in `covey-config`.
+ */
+const otherCharToKeyCode = (key: string): KeyCode | undefined => {
+  const chars = [...key];
+  if (chars.length !== 1 || /^[\x00-\x7f\s\p{Cc}]$/u.test(key)) return;
+  return key;
+};
+
+/**
+ * Converts the `code` of a keyboard event, which is the position of the key
+ * on a US QWERTY keyboard.
+ */
+export const codeToKeyCode = (code: string): KeyCode | undefined => {
+  if (/^Key[A-Z]$/.test(code)) {
+    return code.slice("Key".length).toLowerCase();
+  }
+  if (/^Digit[0-9]$/.test(code)) {
+    return code.toLowerCase();
+  }
+  if (/^Numpad[0-9]$/.test(code)) {
+    return code.toLowerCase();
+  }
+  switch (code) {
+    case "Backquote":
+      return "backtick";
+    case "Minus":
+      return "hyphen";
+    case "Equal":
+      return "equal";
+    case "BracketLeft":
+      return "left-bracket";
+    case "BracketRight":
+      return "right-bracket";
+    case "Backslash":
+      return "backslash";
+    case "Semicolon":
+      return "semicolon";
+    case "Quote":
+      return "apostrophe";
+    case "Comma":
+      return "comma";
+    case "Period":
+      return "period";
+    case "Slash":
+      return "slash";
+    case "Space":
+      return "space";
+    case "NumpadAdd":
+      return "numpad-add";
+    case "NumpadSubtract":
+      return "numpad-subtract";
+    case "NumpadMultiply":
+      return "numpad-multiply";
+    case "NumpadDivide":
+      return "numpad-divide";
+    case "NumpadDecimal":
+      return "numpad-decimal";
+    case "NumpadEnter":
+      return "numpad-enter";
+    default:
+      // the rest are named the same as their `key`
+      return symbolToKeyCode(code);
+  }
+};
+
+/**
+ * Converts the `key` of a keyboard event on the numpad.
+ *
+ * Returns `undefined` if the key is not a numpad key, for example when num
+ * lock is off and the numpad produces arrow keys.
+ */
+export const numpadSymbolToKeyCode = (
+  symbol: string,
+): KeyCode | undefined => {
+  if (/^[0-9]$/.test(symbol)) {
+    return ("numpad" + symbol) as KeyCode;
+  }
+
+  switch (symbol) {
+    case "+":
+      return "numpad-add";
+    case "-":
+      return "numpad-subtract";
+    case "*":
+      return "numpad-multiply";
+    case "/":
+      return "numpad-divide";
+    case ".":
+    case ",":
+      return "numpad-decimal";
+    case "Enter":
+      return "numpad-enter";
     default:
       return;
   }
@@ -78,6 +204,11 @@ export const nameToSymbol = (name: KeyCode): string => {
     return name.slice("digit".length);
   }
 
+  // numpad numbers
+  if (/^numpad\d$/.test(name)) {
+    return "Num " + name.slice("numpad".length);
+  }
+
   // letters
   if (/^[a-z]$/.test(name)) {
     return name.toLocaleUpperCase();
@@ -116,8 +247,50 @@ export const nameToSymbol = (name: KeyCode): string => {
       return ".";
     case "slash":
       return "/";
-    default:
-      throw new Error("all key names should be matched");
+    case "escape":
+      return "Esc";
+    case "space":
+      return "Space";
+    case "backspace":
+      return "⌫";
+    case "delete":
+      return "Del";
+    case "insert":
+      return "Ins";
+    case "arrow-up":
+      return "↑";
+    case "arrow-down":
+      return "↓";
+    case "arrow-left":
+      return "←";
+    case "arrow-right":
+      return "→";
+    case "home":
+      return "Home";
+    case "end":
+      return "End";
+    case "page-up":
+      return "PgUp";
+    case "page-down":
+      return "PgDn";
+    case "numpad-add":
+      return "Num +";
+    case "numpad-subtract":
+      return "Num -";
+    case "numpad-multiply":
+      return "Num *";
+    case "numpad-divide":
+      return "Num /";
+    case "numpad-decimal":
+      return "Num .";
+    case "numpad-enter":
+      return "Num ↵";
+    default: {
+      // characters that aren't on a US QWERTY keyboard, which stay
+      // lowercase if they don't have a single uppercase character, like "ß"
+      const upper = name.toLocaleUpperCase();
+      return [...upper].length === 1 ? upper : name;
+    }
   }
 };
 
@@ -129,17 +302,80 @@ export const hotkeysEqual = (
   a.ctrl === b.ctrl &&
   a.shift === b.shift &&
   a.alt === b.alt &&
-  a.meta === b.meta;
+  a.meta === b.meta &&
+  a.mode === b.mode;
+
+/**
+ * A press that matches `hotkey`, for activating a hotkey without pressing
+ * it.
+ */
+export const keyPressOf = (hotkey: DeepReadonly<Hotkey>): KeyPress => ({
+  physical: hotkey.key,
+  logical: hotkey.key,
+  ctrl: hotkey.ctrl,
+  alt: hotkey.alt,
+  shift: hotkey.shift,
+  meta: hotkey.meta,
+});
+
+/**
+ * The hotkey to show for a key that was pressed, which is the character it
+ * typed if there is one.
+ */
+export const hotkeyOfKeyPress = (
+  press: DeepReadonly<KeyPress>,
+): Hotkey | undefined => {
+  const key = press.logical ?? press.physical;
+  if (key === null) return;
+
+  return {
+    key,
+    ctrl: press.ctrl,
+    alt: press.alt,
+    shift: press.shift,
+    meta: press.meta,
+    mode: press.logical === null ? "physical" : "logical",
+  };
+};
+
+const logicalKey = (ev: KeyboardEvent): KeyCode | undefined =>
+  (ev.location === KeyboardEvent.DOM_KEY_LOCATION_NUMPAD
+    ? numpadSymbolToKeyCode(ev.key)
+    : undefined) ?? symbolToKeyCode(ev.key);
 
 /**
- * Converts a `KeyboardEvent` into a `Hotkey`.
+ * Converts a `KeyboardEvent` into a `KeyPress`.
+ *
+ * Returns `undefined` if neither the key nor its position is a recognised
+ * hotkey keycode.
+ */
+export const keyPressFromKeyboardEvent = (
+  ev: KeyboardEvent,
+): KeyPress | undefined => {
+  const physical = codeToKeyCode(ev.code) ?? null;
+  const logical = logicalKey(ev) ?? null;
+  if (physical === null && logical === null) return;
+
+  return {
+    physical,
+    logical,
+    ctrl: ev.ctrlKey,
+    alt: ev.altKey,
+    shift: ev.shiftKey,
+    meta: ev.metaKey,
+  };
+};
+
+/**
+ * Converts a `KeyboardEvent` into a `Hotkey` with the given mode.
  *
  * Returns `undefined` if the key is not a recognised hotkey keycode.
  */
 export const hotkeyFromKeyboardEvent = (
   ev: KeyboardEvent,
+  mode: KeyMode = "logical",
 ): Hotkey | undefined => {
-  const key = symbolToKeyCode(ev.key);
+  const key = mode === "physical" ? codeToKeyCode(ev.code) : logicalKey(ev);
   if (key === undefined) return;
 
   return {
@@ -148,9 +384,53 @@ export const hotkeyFromKeyboardEvent = (
     alt: ev.altKey,
     shift: ev.shiftKey,
     meta: ev.metaKey,
+    mode,
   };
 };
 
+/**
+ * Whether the launcher uses this hotkey itself, so it can't be bound to a
+ * command.
+ *
+ * This must match `Hotkey::is_reserved` in `covey-config`.
+ */
+export const isReserved = (hotkey: DeepReadonly<Hotkey>): boolean =>
+  !hotkey.ctrl &&
+  !hotkey.alt &&
+  !hotkey.meta &&
+  [
+    "escape",
+    "space",
+    "backspace",
+    "delete",
+    "arrow-up",
+    "arrow-down",
+    "arrow-left",
+    "arrow-right",
+    "home",
+    "end",
+  ].includes(hotkey.key);
+
+/**
+ * Whether a keyboard event types into or edits the input by default, such
+ * as a character, ctrl+backspace or shift+home.
+ *
+ * These aren't prevented while waiting for the host to match them against
+ * the hotkeys, as the input would miss them if they aren't hotkeys.
+ */
+export const editsInput = (ev: KeyboardEvent): boolean =>
+  !ev.altKey &&
+  !ev.metaKey &&
+  ([...ev.key].length === 1 ||
+    [
+      "Backspace",
+      "Delete",
+      "ArrowLeft",
+      "ArrowRight",
+      "Home",
+      "End",
+    ].includes(ev.key));
+
 /**
  * Hotkey where `key` can also be undefined.
  */
@@ -163,6 +443,7 @@ export const isEmpty = (
   alt: false;
   shift: false;
   meta: false;
+  mode: KeyMode;
 } =>
   hotkey.key === undefined &&
   !hotkey.ctrl &&
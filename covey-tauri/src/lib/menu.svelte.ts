@@ -3,32 +3,59 @@
 import { Channel, invoke } from "@tauri-apps/api/core";
 
 import type {
-  Command,
   Event,
   Hotkey,
+  HotkeyPress,
   Id,
+  ItemCommand,
+  JsonValue,
+  KeyPress,
   ListItem,
   ListStyle,
+  Page,
   PluginManifest,
+  Preview,
+  Section,
 } from "./bindings";
 import * as keys from "./keys";
 import { Settings } from "./settings.svelte";
 import type { DeepReadonly } from "./utils";
 
-export type CommandInfo = Command & { customHotkey?: Hotkey };
+export type Confirmation = Omit<Extract<Event, { kind: "confirm" }>, "kind">;
+export type ParameterRequest = Omit<
+  Extract<Event, { kind: "collectParameters" }>,
+  "kind"
+>;
 
 export class Menu {
   public items = $state<ListItem[]>([]);
   public style = $state<ListStyle | undefined>();
+  /** Section headers, keyed by the index of their first item. */
+  public sections = $state<Map<number, Section>>(new Map());
   public activePlugin = $state<Id | undefined>();
+  public page = $state<Page | undefined>();
+  /** A confirmation that the user needs to respond to. */
+  public confirmation = $state<Confirmation | undefined>();
+  /** Parameters of a command that the user needs to enter. */
+  public parameterRequest = $state<ParameterRequest | undefined>();
+  public parameterValues = $state<Record<string, JsonValue>>({});
+  /** Error from the last submission of `parameterValues`. */
+  public parameterError = $state<string | undefined>();
   public selection = $state<number>(0);
+  /** Preview of the selected item, if it has one. */
+  public preview = $state<Preview | undefined>();
   public inputText = $state<string>("");
   // this is only updated by plugins, so no need to keep live
   // with the actual selection when changed by UI
   public textSelection = $state<[number, number]>([0, 0]);
+  /** Hotkeys pressed so far of an unfinished hotkey sequence. */
+  public pendingHotkeys = $state<Hotkey[]>([]);
 
   // definitely assigned in `new`.
   private settings!: Settings;
+  /** Incremented on each preview request, to ignore outdated previews. */
+  private previewRequests = 0;
+  private pendingHotkeysTimeout: ReturnType<typeof setTimeout> | undefined;
 
   private constructor() {}
 
@@ -46,11 +73,32 @@ export class Menu {
         case "setList":
           self.items = msg.items;
           self.style = msg.style ?? undefined;
+          self.sections = new Map(msg.sections.map((s) => [s.start, s]));
           self.activePlugin = msg.plugin_id;
           self.selection = 0;
           break;
+        case "confirm":
+          self.confirmation = {
+            id: msg.id,
+            message: msg.message,
+            plugin_id: msg.plugin_id,
+          };
+          break;
+        case "collectParameters":
+          self.parameterRequest = {
+            id: msg.id,
+            command: msg.command,
+            plugin_id: msg.plugin_id,
+          };
+          self.parameterValues = {};
+          self.parameterError = undefined;
+          break;
+        case "setPage":
+          self.page = msg.page ?? undefined;
+          break;
         case "reload":
           self.items = [];
+          self.sections = new Map();
           self.selection = 0;
           // re-query the current input
           self.query();
@@ -68,54 +116,130 @@ export class Menu {
     void invoke("query", { text: this.inputText });
   }
 
-  public activateById(commandId: string) {
-    void invoke("activate", {
-      listItemId: this.items[this.selection].id,
-      commandName: commandId,
-    });
+  /**
+   * Fetches the preview of the selected item.
+   */
+  public async updatePreview() {
+    const request = ++this.previewRequests;
+    const item = this.currentItem();
+    if (item == null || !item.hasPreview) {
+      this.preview = undefined;
+      return;
+    }
+
+    try {
+      const preview = await invoke<Preview | null>("preview", {
+        listItemId: item.id,
+      });
+      if (request !== this.previewRequests) return;
+      this.preview = preview ?? undefined;
+    } catch (e) {
+      console.error("failed to get preview", e);
+      if (request !== this.previewRequests) return;
+      this.preview = undefined;
+    }
+  }
+
+  /**
+   * Responds to the current confirmation, if there is one.
+   */
+  public respondToConfirmation(accepted: boolean) {
+    if (this.confirmation == null) return;
+
+    void invoke("confirm", { id: this.confirmation.id, accepted });
+    this.confirmation = undefined;
   }
 
   /**
-   * Tries to activate a command from a keyboard event.
+   * Submits the entered parameters, or cancels the command if `submit`
+   * is false.
    *
-   * Returns `true` if something was activated.
+   * The request stays open if the values are invalid.
+   */
+  public async submitParameters(submit: boolean) {
+    if (this.parameterRequest == null) return;
+
+    try {
+      await invoke("submit_parameters", {
+        id: this.parameterRequest.id,
+        values: submit ? this.parameterValues : null,
+      });
+      this.parameterRequest = undefined;
+    } catch (e) {
+      this.parameterError = String(e);
+    }
+  }
+
+  /**
+   * Closes the current page.
    *
-   * This may also reload the plugin if the hotkey matches.
+   * Returns `false` if there was no page to close.
    */
-  public activateByEvent(ev: KeyboardEvent): boolean {
-    const pressedHotkey = keys.hotkeyFromKeyboardEvent(ev);
-    if (pressedHotkey == null) return false;
+  public async popPage(): Promise<boolean> {
+    return await invoke<boolean>("pop_page");
+  }
 
-    return this.activateByHotkey(pressedHotkey);
+  /**
+   * Goes back to the previous query in the history.
+   *
+   * Returns `false` if there was no older query.
+   */
+  public async historyPrevious(): Promise<boolean> {
+    return await invoke<boolean>("history_previous");
   }
 
-  public activateByHotkey(pressedHotkey: Hotkey): boolean {
-    // check reload
-    if (
-      keys.hotkeysEqual(
-        pressedHotkey,
-        this.settings.globalConfig.app["reload-hotkey"],
-      )
-    ) {
-      void invoke("reload_plugin", {
-        pluginId: this.activePlugin,
-      }).then(() => this.query());
-    }
+  /**
+   * Goes forward to the next query in the history.
+   *
+   * Returns `false` if the input wasn't from the history.
+   */
+  public async historyNext(): Promise<boolean> {
+    return await invoke<boolean>("history_next");
+  }
 
-    const commands = this.getAvailableCommands();
+  /**
+   * Submitted queries of every plugin that contain `search`, ignoring
+   * case, with the newest first.
+   *
+   * These include the plugin's prefix, so they can be set as the input.
+   */
+  public async searchHistory(search: string): Promise<string[]> {
+    return await invoke<string[]>("search_history", { search });
+  }
 
-    // find a command id that is in the `availableCommands` and matches
-    // the hotkey (either custom or default)
-    const commandId = commands.find((cmd) => {
-      const hotkey = cmd.customHotkey ?? cmd["default-hotkey"];
-      return hotkey != null && keys.hotkeysEqual(hotkey, pressedHotkey);
-    })?.id;
+  public activateById(commandId: string) {
+    void invoke("activate", {
+      listItemId: this.items[this.selection].id,
+      commandName: commandId,
+    });
+  }
 
-    if (commandId == null) return false;
+  /**
+   * Presses a key, which the host matches against the reload hotkey and
+   * the hotkey sequences of the selected item's commands.
+   *
+   * Resolves to `true` if something was activated, or if the key starts a
+   * hotkey sequence.
+   */
+  public async pressHotkey(press: KeyPress): Promise<boolean> {
+    const result = await invoke<HotkeyPress>("press_hotkey", {
+      listItemId: this.currentItem()?.id ?? null,
+      press,
+    });
 
-    // activate the found command
-    this.activateById(commandId);
-    return true;
+    clearTimeout(this.pendingHotkeysTimeout);
+    if (result.kind === "pending") {
+      this.pendingHotkeys = result.pressed.flatMap(
+        (press) => keys.hotkeyOfKeyPress(press) ?? [],
+      );
+      // the host forgets the keys after this
+      this.pendingHotkeysTimeout = setTimeout(() => {
+        this.pendingHotkeys = [];
+      }, result.timeoutMs);
+    } else {
+      this.pendingHotkeys = [];
+    }
+    return result.kind !== "noMatch";
   }
 
   public async showSettingsWindow() {
@@ -127,23 +251,8 @@ export class Menu {
   }
 
   /// Returns an empty list if there is not currently selected item
-  public getAvailableCommands(): DeepReadonly<CommandInfo[]> {
-    // get the config of the currently focused plugin
-    const currentItem = this.currentItem();
-    if (currentItem == null) return [];
-    const pluginId = currentItem.id.pluginId;
-    const pluginConfig = this.settings.getPlugin(pluginId);
-    const pluginManifest = this.settings.manifests[pluginId];
-
-    return (
-      pluginManifest.commands
-        .filter((cmd) => currentItem.availableCommands.includes(cmd.id))
-        // add custom hotkey to the info
-        .map((cmd) => ({
-          ...cmd,
-          customHotkey: pluginConfig?.commands[cmd.id],
-        }))
-    );
+  public getAvailableCommands(): DeepReadonly<ItemCommand[]> {
+    return this.currentItem()?.commands ?? [];
   }
 
   public manifestOf(plugin: Id): DeepReadonly<PluginManifest> | undefined {
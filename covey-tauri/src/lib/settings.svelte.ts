@@ -2,6 +2,7 @@ import { invoke } from "@tauri-apps/api/core";
 
 import type {
   GlobalConfig,
+  HotkeyConflict,
   Id,
   PluginConfig,
   PluginManifest,
@@ -12,6 +13,8 @@ export class Settings {
   // definitely assigned in constructor so will not be undefined
   public globalConfig = $state() as GlobalConfig;
   public manifests = $state() as DeepReadonly<Record<string, PluginManifest>>;
+  /** Hotkeys in the config that hide each other. */
+  public hotkeyConflicts = $state<HotkeyConflict[]>([]);
 
   private constructor(
     config: GlobalConfig,
@@ -37,6 +40,7 @@ export class Settings {
     console.debug("received manifests", manifests);
 
     const self = new Settings(config, Object.fromEntries(manifests));
+    self.hotkeyConflicts = await invoke("get_hotkey_conflicts");
     return self;
   }
 
@@ -44,6 +48,8 @@ export class Settings {
     console.debug("updating config to new");
     void invoke("set_global_config", {
       config: this.globalConfig,
+    }).then(async () => {
+      this.hotkeyConflicts = await invoke("get_hotkey_conflicts");
     });
   }
 
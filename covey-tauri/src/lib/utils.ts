@@ -1,3 +1,5 @@
+import type { Highlight } from "./bindings";
+
 // https://stackoverflow.com/a/49670389
 export type DeepReadonly<T> =
   // array
@@ -33,3 +35,31 @@ export class UnreachableError extends Error {
 export const unreachable = (x: never): never => {
   throw new UnreachableError(x);
 };
+
+export type TextSegment = { text: string; highlighted: boolean };
+
+/**
+ * Splits text into segments which are either highlighted or not.
+ *
+ * Highlight indices count code points, so this can't use `String.slice`.
+ */
+export const highlightSegments = (
+  text: string,
+  highlights: readonly Highlight[],
+): TextSegment[] => {
+  const chars = Array.from(text);
+  const segments: TextSegment[] = [];
+  let last = 0;
+  for (const { start, end } of highlights) {
+    if (start < last) continue;
+    if (start > last) {
+      segments.push({ text: chars.slice(last, start).join(""), highlighted: false });
+    }
+    segments.push({ text: chars.slice(start, end).join(""), highlighted: true });
+    last = end;
+  }
+  if (last < chars.length) {
+    segments.push({ text: chars.slice(last).join(""), highlighted: false });
+  }
+  return segments;
+};
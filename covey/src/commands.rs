@@ -0,0 +1,329 @@
+//! Resolving the commands that a list item has.
+
+use std::{collections::HashMap, iter, time::Instant};
+
+use covey_config::{
+    hotkey::{HotkeySequence, KeyPress, SequenceMatch, SequenceMatcher},
+    keyed_list::Id,
+    manifest::PluginManifest,
+};
+
+use crate::DynamicCommand;
+
+/// A command that can be activated on a list item.
+///
+/// Get these with [`ListItem::commands`].
+///
+/// [`ListItem::commands`]: crate::ListItem::commands
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemCommand {
+    /// Command name to activate this with.
+    pub id: String,
+    pub title: String,
+    pub description: Option<String>,
+    /// Hotkey that activates this command.
+    ///
+    /// This is the user's hotkey for the command if they set one,
+    /// otherwise the command's default hotkey. Hotkeys that are
+    /// [reserved](HotkeySequence::is_reserved) by the launcher are left
+    /// out. They are reported as [`HotkeyConflict::Reserved`] if they're
+    /// from the config or manifest.
+    ///
+    /// [`HotkeyConflict::Reserved`]: covey_config::conflicts::HotkeyConflict::Reserved
+    pub hotkey: Option<HotkeySequence>,
+}
+
+/// Commands of a list item, in order.
+///
+/// Manifest commands are listed first, in the order of the manifest, and
+/// only if they are in `available`. Dynamic commands are listed after them.
+pub(crate) fn resolve(
+    manifest: &PluginManifest,
+    user_hotkeys: &HashMap<Id, HotkeySequence>,
+    available: &[String],
+    dynamic: &[DynamicCommand],
+) -> Vec<ItemCommand> {
+    let manifest_commands = manifest
+        .commands
+        .iter()
+        .filter(|command| available.iter().any(|id| id == command.id.as_str()))
+        .map(|command| ItemCommand {
+            id: command.id.as_str().to_owned(),
+            title: command.title.clone(),
+            description: command.description.clone(),
+            hotkey: user_hotkeys
+                .get(&command.id)
+                .or(command.default_hotkey.as_ref())
+                .filter(|hotkey| !hotkey.is_reserved())
+                .cloned(),
+        });
+
+    // these aren't in the manifest, so the user can't change their hotkeys
+    let dynamic_commands = dynamic.iter().map(|command| ItemCommand {
+        id: command.id.clone(),
+        title: command.title.clone(),
+        description: None,
+        hotkey: command
+            .hotkey
+            .clone()
+            .filter(|hotkey| !hotkey.is_reserved()),
+    });
+
+    manifest_commands.chain(dynamic_commands).collect()
+}
+
+/// Something that a hotkey sequence does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HotkeyTarget<'a> {
+    /// Re-initialises the plugin of the list that is shown.
+    Reload,
+    Command(&'a ItemCommand),
+}
+
+/// Presses a key, matching the keys pressed so far against the reload
+/// hotkey and the hotkey of each command.
+///
+/// If several commands have the same hotkey, the first one wins. The reload
+/// hotkey wins over all of them.
+pub(crate) fn press<'a>(
+    matcher: &mut SequenceMatcher,
+    reload_hotkey: &HotkeySequence,
+    commands: &'a [ItemCommand],
+    press: KeyPress,
+    now: Instant,
+) -> SequenceMatch<HotkeyTarget<'a>> {
+    let targets: Vec<_> = iter::once((HotkeyTarget::Reload, Some(reload_hotkey)))
+        .chain(
+            commands
+                .iter()
+                .map(|command| (HotkeyTarget::Command(command), command.hotkey.as_ref())),
+        )
+        .collect();
+    matcher
+        .press(press, now, &targets, |(_, hotkey)| *hotkey)
+        .map(|(target, _)| *target)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, time::Instant};
+
+    use covey_config::{
+        hotkey::{Hotkey, HotkeySequence, KeyPress, SequenceMatch, SequenceMatcher},
+        keyed_list::Id,
+        manifest::PluginManifest,
+    };
+
+    use super::{press, resolve, HotkeyTarget, ItemCommand};
+    use crate::DynamicCommand;
+
+    fn manifest() -> PluginManifest {
+        PluginManifest::try_from_toml(
+            r#"
+            name = "Test"
+
+            [[commands]]
+            id = "open"
+            title = "Open"
+            default-hotkey = { key = "enter" }
+
+            [[commands]]
+            id = "copy"
+            title = "Copy"
+            description = "Copy the path"
+            default-hotkey = { key = "c", ctrl = true }
+
+            [[commands]]
+            id = "delete"
+            title = "Delete"
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn hotkey(s: &str) -> HotkeySequence {
+        s.parse().unwrap()
+    }
+
+    fn key(s: &str) -> KeyPress {
+        KeyPress::from(s.parse::<Hotkey>().unwrap())
+    }
+
+    /// ID of the command that pressing `s` on its own activates, or
+    /// `"reload"` for the reload hotkey, which is ctrl+r.
+    fn find<'a>(commands: &'a [ItemCommand], s: &str) -> Option<&'a str> {
+        let mut matcher = SequenceMatcher::default();
+        let reload_hotkey = hotkey("ctrl+r");
+        match press(
+            &mut matcher,
+            &reload_hotkey,
+            commands,
+            key(s),
+            Instant::now(),
+        ) {
+            SequenceMatch::Matched(target) => Some(target_id(target)),
+            SequenceMatch::Pending | SequenceMatch::NoMatch => None,
+        }
+    }
+
+    fn target_id(target: HotkeyTarget<'_>) -> &str {
+        match target {
+            HotkeyTarget::Reload => "reload",
+            HotkeyTarget::Command(command) => command.id.as_str(),
+        }
+    }
+
+    fn available(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| (*id).to_owned()).collect()
+    }
+
+    #[test]
+    fn only_available_commands_in_manifest_order() {
+        let commands = resolve(
+            &manifest(),
+            &HashMap::new(),
+            &available(&["delete", "open", "unknown"]),
+            &[],
+        );
+
+        let ids: Vec<_> = commands.iter().map(|command| command.id.as_str()).collect();
+        assert_eq!(ids, ["open", "delete"]);
+        assert_eq!(commands[0].hotkey, Some(hotkey("enter")));
+        assert_eq!(commands[1].hotkey, None);
+    }
+
+    #[test]
+    fn user_hotkeys_override_defaults() {
+        let user_hotkeys = HashMap::from([
+            (Id::new("copy"), hotkey("alt+c")),
+            (Id::new("delete"), hotkey("ctrl+d")),
+        ]);
+        let commands = resolve(
+            &manifest(),
+            &user_hotkeys,
+            &available(&["open", "copy", "delete"]),
+            &[],
+        );
+
+        let hotkeys: Vec<_> = commands
+            .iter()
+            .map(|command| command.hotkey.clone())
+            .collect();
+        assert_eq!(
+            hotkeys,
+            [
+                Some(hotkey("enter")),
+                Some(hotkey("alt+c")),
+                Some(hotkey("ctrl+d"))
+            ]
+        );
+        assert_eq!(commands[1].description.as_deref(), Some("Copy the path"));
+    }
+
+    #[test]
+    fn dynamic_commands_are_last() {
+        let dynamic = [DynamicCommand {
+            id: "#0".to_owned(),
+            title: "Star".to_owned(),
+            hotkey: Some(hotkey("ctrl+s")),
+        }];
+        let commands = resolve(
+            &manifest(),
+            &HashMap::new(),
+            &available(&["open"]),
+            &dynamic,
+        );
+
+        let ids: Vec<_> = commands.iter().map(|command| command.id.as_str()).collect();
+        assert_eq!(ids, ["open", "#0"]);
+        assert_eq!(commands[1].title, "Star");
+        assert_eq!(commands[1].description, None);
+    }
+
+    #[test]
+    fn finds_command_by_effective_hotkey() {
+        let user_hotkeys = HashMap::from([(Id::new("copy"), hotkey("alt+c"))]);
+        let dynamic = [DynamicCommand {
+            id: "#0".to_owned(),
+            title: "Star".to_owned(),
+            hotkey: Some(hotkey("ctrl+s")),
+        }];
+        let commands = resolve(
+            &manifest(),
+            &user_hotkeys,
+            &available(&["open", "copy"]),
+            &dynamic,
+        );
+
+        assert_eq!(find(&commands, "enter"), Some("open"));
+        assert_eq!(find(&commands, "alt+c"), Some("copy"));
+        assert_eq!(
+            find(&commands, "ctrl+c"),
+            None,
+            "default is replaced by the user's hotkey"
+        );
+        assert_eq!(find(&commands, "ctrl+s"), Some("#0"));
+        assert_eq!(find(&commands, "shift+enter"), None);
+    }
+
+    #[test]
+    fn reserved_hotkeys_are_left_out() {
+        let user_hotkeys = HashMap::from([(Id::new("copy"), hotkey("down"))]);
+        let dynamic = [DynamicCommand {
+            id: "#0".to_owned(),
+            title: "Close".to_owned(),
+            hotkey: Some(hotkey("escape")),
+        }];
+        let commands = resolve(&manifest(), &user_hotkeys, &available(&["copy"]), &dynamic);
+
+        assert_eq!(commands[0].hotkey, None);
+        assert_eq!(commands[1].hotkey, None);
+        assert_eq!(find(&commands, "escape"), None);
+    }
+
+    #[test]
+    fn first_command_wins_on_duplicate_hotkeys() {
+        let dynamic = [DynamicCommand {
+            id: "#0".to_owned(),
+            title: "Also open".to_owned(),
+            hotkey: Some(hotkey("enter")),
+        }];
+        let commands = resolve(
+            &manifest(),
+            &HashMap::new(),
+            &available(&["open"]),
+            &dynamic,
+        );
+
+        assert_eq!(find(&commands, "enter"), Some("open"));
+    }
+
+    #[test]
+    fn user_hotkey_sequences_are_pending_until_finished() {
+        let user_hotkeys = HashMap::from([(Id::new("delete"), hotkey("ctrl+k ctrl+d"))]);
+        let commands = resolve(
+            &manifest(),
+            &user_hotkeys,
+            &available(&["copy", "delete"]),
+            &[],
+        );
+
+        let mut matcher = SequenceMatcher::default();
+        let reload_hotkey = hotkey("ctrl+r");
+        let now = Instant::now();
+        let mut press_key =
+            |s: &str| press(&mut matcher, &reload_hotkey, &commands, key(s), now).map(target_id);
+        assert_eq!(press_key("ctrl+k"), SequenceMatch::Pending);
+        assert_eq!(press_key("ctrl+d"), SequenceMatch::Matched("delete"));
+        assert_eq!(press_key("ctrl+c"), SequenceMatch::Matched("copy"));
+    }
+
+    #[test]
+    fn reload_hotkey_wins_over_commands() {
+        let user_hotkeys = HashMap::from([(Id::new("copy"), hotkey("ctrl+r"))]);
+        let commands = resolve(&manifest(), &user_hotkeys, &available(&["copy"]), &[]);
+
+        assert_eq!(find(&commands, "ctrl+r"), Some("reload"));
+        assert_eq!(find(&[], "ctrl+r"), Some("reload"));
+    }
+}
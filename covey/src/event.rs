@@ -1,17 +1,35 @@
 //! Actions returned by a plugin.
 
 use core::fmt;
+use std::{
+    collections::HashMap,
+    ops::Range,
+    path::{Path, PathBuf},
+};
 
 use az::SaturatingAs as _;
 
-use crate::{proto, Plugin};
+use covey_config::hotkey::HotkeySequence;
+
+use crate::{
+    commands::{self, ItemCommand},
+    proto, Plugin,
+};
 
 /// Event returned by a plugin.
 pub(crate) enum PluginEvent {
     /// Set the displayed list.
-    SetList { list: List, index: u64 },
-    /// Run a sequence of actions.
-    Run(Vec<Action>),
+    SetList {
+        list: List,
+        /// The input that the list is the result of.
+        query: String,
+        index: u64,
+    },
+    /// Run a sequence of actions returned by a plugin.
+    Run {
+        plugin: Plugin,
+        actions: Vec<Action>,
+    },
 }
 
 impl fmt::Debug for PluginEvent {
@@ -21,7 +39,11 @@ impl fmt::Debug for PluginEvent {
                 .debug_tuple("PluginEvent::SetList")
                 .field(&format!("{} items", list.len()))
                 .finish(),
-            Self::Run(actions) => f.debug_tuple("PluginEvent::Run").field(actions).finish(),
+            Self::Run { plugin, actions } => f
+                .debug_struct("PluginEvent::Run")
+                .field("plugin", plugin)
+                .field("actions", actions)
+                .finish(),
         }
     }
 }
@@ -29,10 +51,167 @@ impl fmt::Debug for PluginEvent {
 #[derive(Debug)]
 pub(crate) enum Action {
     Close,
-    RunCommand(String, Vec<String>),
+    RunCommand(Command),
     RunShell(String),
+    RunCaptured(CapturedCommand),
+    RunInTerminal {
+        command: Command,
+        hold: bool,
+    },
     Copy(String),
+    Paste(String),
+    /// The input is not prefixed with the plugin's prefix yet.
     SetInput(Input),
+    PushPage(Page),
+    Confirm {
+        message: String,
+        actions: Vec<Action>,
+    },
+    Notify(Notification),
+}
+
+/// A command whose output is captured by the host.
+#[derive(Debug)]
+pub(crate) struct CapturedCommand {
+    pub(crate) program: Program,
+    /// ID to send the output back to the plugin with, if the plugin
+    /// wants it.
+    pub(crate) callback_id: Option<u64>,
+}
+
+#[derive(Debug)]
+pub(crate) enum Program {
+    Command(Command),
+    Shell(String),
+}
+
+/// A command to run, with it's environment.
+#[derive(Debug)]
+pub(crate) struct Command {
+    pub(crate) cmd: String,
+    pub(crate) args: Vec<String>,
+    pub(crate) cwd: Option<PathBuf>,
+    /// Extra environment variables to set.
+    pub(crate) env: HashMap<String, String>,
+}
+
+impl Command {
+    pub(crate) fn from_proto(proto: proto::Command) -> Self {
+        Self {
+            cmd: proto.cmd,
+            args: proto.args,
+            cwd: proto.cwd.map(PathBuf::from),
+            env: proto.env,
+        }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.cmd)?;
+        for arg in &self.args {
+            write!(f, " {arg}")?;
+        }
+        Ok(())
+    }
+}
+
+impl CapturedCommand {
+    pub(crate) fn from_proto(proto: proto::RunCaptured) -> Option<Self> {
+        let program = match proto.program? {
+            proto::run_captured::Program::Command(command) => {
+                Program::Command(Command::from_proto(command))
+            }
+            proto::run_captured::Program::Shell(str) => Program::Shell(str),
+        };
+
+        Some(Self {
+            program,
+            callback_id: proto.callback_id,
+        })
+    }
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Command(command) => write!(f, "{command}"),
+            Self::Shell(str) => write!(f, "{str}"),
+        }
+    }
+}
+
+/// A request for the user to confirm an activation.
+///
+/// The frontend should respond with [`Host::confirm`].
+///
+/// [`Host::confirm`]: crate::Host::confirm
+#[derive(Debug, Clone)]
+pub struct Confirmation {
+    /// ID to pass back to [`Host::confirm`].
+    ///
+    /// [`Host::confirm`]: crate::Host::confirm
+    pub id: u64,
+    pub plugin: Plugin,
+    /// Message to show the user, e.g. "Delete 3 files?".
+    pub message: String,
+}
+
+/// A request for the user to enter the parameters of a command before it
+/// is activated.
+///
+/// The frontend should respond with [`Host::submit_parameters`].
+///
+/// [`Host::submit_parameters`]: crate::Host::submit_parameters
+#[derive(Debug, Clone)]
+pub struct ParameterRequest {
+    /// ID to pass back to [`Host::submit_parameters`].
+    ///
+    /// [`Host::submit_parameters`]: crate::Host::submit_parameters
+    pub id: u64,
+    pub plugin: Plugin,
+    /// The command to be activated, with the parameters to ask for.
+    pub command: covey_config::manifest::Command,
+}
+
+/// A desktop notification requested by a plugin.
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub title: String,
+    /// May be empty.
+    pub body: String,
+    /// A named icon, taken from the system.
+    pub icon: Option<String>,
+    pub urgency: Urgency,
+}
+
+impl Notification {
+    pub(crate) fn from_proto(proto: proto::Notification) -> Self {
+        Self {
+            urgency: Urgency::from_proto(proto.urgency()),
+            title: proto.title,
+            body: proto.body,
+            icon: proto.icon,
+        }
+    }
+}
+
+/// How important a notification is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Urgency {
+    Low,
+    Normal,
+    Critical,
+}
+
+impl Urgency {
+    pub(crate) fn from_proto(proto: proto::Urgency) -> Self {
+        match proto {
+            proto::Urgency::Low => Self::Low,
+            proto::Urgency::Normal => Self::Normal,
+            proto::Urgency::Critical => Self::Critical,
+        }
+    }
 }
 
 /// The main text input contents and selection.
@@ -44,6 +223,15 @@ pub struct Input {
 }
 
 impl Input {
+    /// An input with the cursor placed at the end.
+    pub fn new(contents: String) -> Self {
+        let len = contents.chars().count().saturating_as();
+        Self {
+            contents,
+            selection: (len, len),
+        }
+    }
+
     pub(crate) fn prefix_with(&mut self, prefix: &str) {
         self.contents.insert_str(0, prefix);
         let prefix_len =
@@ -53,13 +241,40 @@ impl Input {
         self.selection = (a.saturating_add(prefix_len), b.saturating_add(prefix_len));
     }
 
-    pub(crate) fn from_proto(plugin: &Plugin, il: proto::Input) -> Self {
-        let mut input = Self {
+    /// Converts the input without adding the plugin's prefix.
+    pub(crate) fn from_proto(il: proto::Input) -> Self {
+        Self {
             contents: il.query,
             selection: (il.range_lb.saturating_as(), il.range_ub.saturating_as()),
-        };
-        input.prefix_with(plugin.prefix());
-        input
+        }
+    }
+}
+
+/// A page pushed by a plugin, shown on top of the main list.
+///
+/// While a page is open, the entire input is sent to the page's plugin
+/// without needing the plugin's prefix.
+#[derive(Debug, Clone)]
+pub struct Page {
+    pub plugin: Plugin,
+    /// ID unique within the plugin.
+    pub local_id: u64,
+    pub title: Option<String>,
+    /// Placeholder of the input when it is empty.
+    pub placeholder: Option<String>,
+    /// Initial input when the page is opened.
+    pub input: Input,
+}
+
+impl Page {
+    pub(crate) fn from_proto(plugin: &Plugin, proto: proto::Page) -> Self {
+        Self {
+            plugin: plugin.clone(),
+            local_id: proto.id,
+            title: proto.title,
+            placeholder: proto.placeholder,
+            input: Input::from_proto(proto.input),
+        }
     }
 }
 
@@ -67,8 +282,23 @@ impl Input {
 #[derive(Debug)]
 pub struct List {
     pub items: Vec<ListItem>,
+    /// Headers to group the items under, in order of [`Section::start`].
+    ///
+    /// Items before the first section have no header.
+    pub sections: Vec<Section>,
     pub style: Option<ListStyle>,
     pub plugin: Plugin,
+    /// Whether the plugin asked for the query to be left out of the
+    /// history.
+    pub private: bool,
+}
+
+/// A header shown above a group of list items.
+#[derive(Debug, Clone)]
+pub struct Section {
+    pub title: String,
+    /// Index of the first item in this section.
+    pub start: usize,
 }
 
 impl List {
@@ -81,20 +311,82 @@ impl List {
     }
 
     pub(crate) fn from_proto(plugin: &Plugin, proto: proto::QueryResponse) -> Self {
+        let private = proto.private();
         let style = proto.list_style.map(ListStyle::from_proto);
-        let list: Vec<_> = proto
+        let mut list: Vec<_> = proto
             .items
             .into_iter()
             .map(|li| ListItem::new(Plugin::clone(plugin), li))
             .collect();
+        drop_images_over_budget(&mut list, Icon::MAX_LIST_IMAGE_BYTES, plugin);
+
+        let mut sections: Vec<Section> = vec![];
+        for section in proto.sections {
+            let start = section.start.saturating_as::<usize>();
+            let in_order = sections.last().is_none_or(|last| last.start <= start);
+            if start >= list.len() || !in_order {
+                tracing::warn!("ignoring invalid section {section:?} from {plugin:?}");
+                continue;
+            }
+            sections.push(Section {
+                title: section.title,
+                start,
+            });
+        }
+
         Self {
             style,
             items: list,
+            sections,
             plugin: plugin.clone(),
+            private,
         }
     }
 }
 
+/// Removes inline images once their total size is more than `budget`,
+/// going through the items in order.
+///
+/// Once the budget is used up, every later image is removed, so the first
+/// items keep their images. Accessories with a removed image keep their
+/// text.
+fn drop_images_over_budget(items: &mut [ListItem], budget: usize, plugin: &Plugin) {
+    let mut total = 0_usize;
+    let mut fits = |bytes: usize| {
+        total = total.saturating_add(bytes);
+        total <= budget
+    };
+
+    let mut dropped = 0;
+    for item in items {
+        if let Some(proto::list_item::Icon::Image(image)) = &item.item.icon {
+            if !fits(image.data.len()) {
+                item.item.icon = None;
+                dropped += 1;
+            }
+        }
+        for accessory in &mut item.accessories {
+            if let Accessory::IconText {
+                icon: Icon::Image { data, .. },
+                text,
+            } = accessory
+            {
+                if !fits(data.len()) {
+                    *accessory = Accessory::Text(std::mem::take(text));
+                    dropped += 1;
+                }
+            }
+        }
+    }
+
+    if dropped > 0 {
+        tracing::warn!(
+            "ignoring {dropped} images of list from {plugin:?}: \
+            images are larger than the maximum of {budget} bytes in total"
+        );
+    }
+}
+
 /// The style to display the list provided by a plugin.
 #[derive(Debug, Clone, Copy)]
 pub enum ListStyle {
@@ -119,11 +411,58 @@ impl ListStyle {
 pub struct ListItem {
     plugin: Plugin,
     item: proto::ListItem,
+    accessories: Vec<Accessory>,
+    dynamic_commands: Vec<DynamicCommand>,
 }
 
 impl ListItem {
-    pub(crate) fn new(plugin: Plugin, item: proto::ListItem) -> Self {
-        Self { plugin, item }
+    pub(crate) fn new(plugin: Plugin, mut item: proto::ListItem) -> Self {
+        if let Some(icon) = &item.icon {
+            if let Err(reason) = Icon::validate_proto(icon) {
+                tracing::warn!(
+                    "ignoring icon of list item {:?} from {plugin:?}: {reason}",
+                    item.title
+                );
+                item.icon = None;
+            }
+        }
+
+        for (field, highlights, text) in [
+            ("title", &mut item.title_highlights, &item.title),
+            (
+                "description",
+                &mut item.description_highlights,
+                &item.description,
+            ),
+        ] {
+            let len = text.chars().count();
+            let before = highlights.len();
+            highlights.retain(|h| h.start < h.end && h.end.saturating_as::<usize>() <= len);
+            if highlights.len() != before {
+                tracing::warn!(
+                    "ignoring {} invalid {field} highlights of list item {:?} from {plugin:?}",
+                    before - highlights.len(),
+                    item.title
+                );
+            }
+        }
+
+        let accessories = std::mem::take(&mut item.accessories)
+            .into_iter()
+            .filter_map(|accessory| Accessory::from_proto(accessory, &plugin))
+            .collect();
+
+        let dynamic_commands = std::mem::take(&mut item.dynamic_commands)
+            .into_iter()
+            .filter_map(|command| DynamicCommand::from_proto(command, &plugin))
+            .collect();
+
+        Self {
+            plugin,
+            item,
+            accessories,
+            dynamic_commands,
+        }
     }
 
     pub fn plugin(&self) -> &Plugin {
@@ -134,6 +473,13 @@ impl ListItem {
         &self.item.title
     }
 
+    /// Identifies the item across queries, e.g. a file path or a URL.
+    ///
+    /// This is the title if the plugin didn't set a key.
+    pub fn key(&self) -> &str {
+        self.item.key.as_deref().unwrap_or(&self.item.title)
+    }
+
     pub fn description(&self) -> &str {
         &self.item.description
     }
@@ -152,6 +498,61 @@ impl ListItem {
     pub fn available_commands(&self) -> &[String] {
         &self.item.available_commands
     }
+
+    /// Commands that are not in the plugin's manifest, which can be
+    /// activated like any of the [`ListItem::available_commands`].
+    pub fn dynamic_commands(&self) -> &[DynamicCommand] {
+        &self.dynamic_commands
+    }
+
+    /// Every command that can be activated on this item, with the hotkeys
+    /// that activate them.
+    ///
+    /// This combines the [`ListItem::available_commands`] from the
+    /// plugin's manifest, the user's hotkeys for them, and the
+    /// [`ListItem::dynamic_commands`].
+    pub fn commands(&self) -> Vec<ItemCommand> {
+        commands::resolve(
+            self.plugin.manifest(),
+            self.plugin.command_hotkeys(),
+            &self.item.available_commands,
+            &self.dynamic_commands,
+        )
+    }
+
+    /// Small extra information to show next to the item, in order.
+    pub fn accessories(&self) -> &[Accessory] {
+        &self.accessories
+    }
+
+    /// Ranges of characters in the title to highlight.
+    ///
+    /// Indices count [`char`]s, not bytes. Ranges are always within the
+    /// title.
+    pub fn title_highlights(&self) -> Vec<Range<usize>> {
+        Self::highlights(&self.item.title_highlights)
+    }
+
+    /// Ranges of characters in the description to highlight.
+    ///
+    /// Same as [`ListItem::title_highlights`].
+    pub fn description_highlights(&self) -> Vec<Range<usize>> {
+        Self::highlights(&self.item.description_highlights)
+    }
+
+    fn highlights(highlights: &[proto::Highlight]) -> Vec<Range<usize>> {
+        highlights
+            .iter()
+            .map(|h| h.start.saturating_as()..h.end.saturating_as())
+            .collect()
+    }
+
+    /// Whether a preview can be fetched with [`Host::preview`].
+    ///
+    /// [`Host::preview`]: crate::Host::preview
+    pub fn has_preview(&self) -> bool {
+        self.item.has_preview()
+    }
 }
 
 impl fmt::Debug for ListItem {
@@ -160,11 +561,152 @@ impl fmt::Debug for ListItem {
             .field("plugin", &self.plugin())
             .field("title", &self.item.title)
             .field("description", &self.item.description)
-            .field("icon", &self.item.icon)
+            .field("icon", &self.icon())
+            .field("accessories", &self.accessories)
+            .field("dynamic_commands", &self.dynamic_commands)
             .finish()
     }
 }
 
+/// A command added to a list item at runtime, instead of being declared in
+/// the plugin's manifest.
+#[derive(Debug, Clone)]
+pub struct DynamicCommand {
+    /// Command name to activate this with.
+    pub id: String,
+    pub title: String,
+    pub hotkey: Option<HotkeySequence>,
+}
+
+impl DynamicCommand {
+    /// Returns [`None`] if the ID clashes with a command in the manifest.
+    ///
+    /// Invalid and [reserved](HotkeySequence::is_reserved) hotkeys are
+    /// dropped.
+    fn from_proto(proto: proto::DynamicCommand, plugin: &Plugin) -> Option<Self> {
+        if plugin.manifest().commands.get(&proto.id).is_some() {
+            tracing::warn!(
+                "ignoring dynamic command {:?} from {plugin:?}: its id is in the manifest",
+                proto.title
+            );
+            return None;
+        }
+
+        let hotkey = proto.hotkey.and_then(|hotkey| {
+            let hotkey: HotkeySequence = hotkey
+                .parse()
+                .inspect_err(|e| {
+                    tracing::warn!(
+                        "ignoring hotkey of dynamic command {:?} from {plugin:?}: {e}",
+                        proto.title
+                    );
+                })
+                .ok()?;
+            if hotkey.is_reserved() {
+                tracing::warn!(
+                    "ignoring hotkey {hotkey} of dynamic command {:?} from {plugin:?}: \
+                    the launcher uses it",
+                    proto.title
+                );
+                return None;
+            }
+            Some(hotkey)
+        });
+
+        Some(Self {
+            id: proto.id,
+            title: proto.title,
+            hotkey,
+        })
+    }
+}
+
+/// Small extra information shown next to a list item.
+#[derive(Debug, Clone)]
+pub enum Accessory {
+    Text(String),
+    /// Text with an icon before it.
+    IconText {
+        icon: Icon,
+        text: String,
+    },
+    /// Text with a coloured background.
+    Tag {
+        text: String,
+        color: TagColor,
+    },
+}
+
+impl Accessory {
+    /// Returns [`None`] if the accessory is empty.
+    ///
+    /// Invalid icons are dropped, leaving just the text.
+    fn from_proto(proto: proto::Accessory, plugin: &Plugin) -> Option<Self> {
+        use proto::{accessory::Kind, icon_text::Icon as IconTextIcon, list_item::Icon as Proto};
+
+        Some(match proto.kind? {
+            Kind::Text(text) => Self::Text(text),
+            Kind::IconText(proto::IconText { icon, text }) => {
+                let icon = icon.map(|icon| match icon {
+                    IconTextIcon::Name(name) => Proto::Name(name),
+                    IconTextIcon::Text(text) => Proto::Text(text),
+                    IconTextIcon::File(path) => Proto::File(path),
+                    IconTextIcon::Image(image) => Proto::Image(image),
+                });
+                match icon {
+                    Some(icon) => match Icon::validate_proto(&icon) {
+                        Ok(()) => Self::IconText {
+                            icon: Icon::from_proto(icon),
+                            text,
+                        },
+                        Err(reason) => {
+                            tracing::warn!(
+                                "ignoring icon of accessory {text:?} from {plugin:?}: {reason}"
+                            );
+                            Self::Text(text)
+                        }
+                    },
+                    None => Self::Text(text),
+                }
+            }
+            Kind::Tag(tag) => Self::Tag {
+                color: TagColor::from_proto(tag.color()),
+                text: tag.text,
+            },
+        })
+    }
+}
+
+/// Colour of an [`Accessory::Tag`].
+///
+/// These should be mapped to colours that fit the frontend's theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagColor {
+    Default,
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+    Grey,
+}
+
+impl TagColor {
+    pub(crate) fn from_proto(proto: proto::TagColor) -> Self {
+        match proto {
+            proto::TagColor::Default => Self::Default,
+            proto::TagColor::Red => Self::Red,
+            proto::TagColor::Orange => Self::Orange,
+            proto::TagColor::Yellow => Self::Yellow,
+            proto::TagColor::Green => Self::Green,
+            proto::TagColor::Blue => Self::Blue,
+            proto::TagColor::Purple => Self::Purple,
+            proto::TagColor::Grey => Self::Grey,
+        }
+    }
+}
+
 /// A list item without rendering details (description, etc).
 ///
 /// Used by the model to call functions on this list item.
@@ -179,18 +721,221 @@ pub struct ListItemId {
     pub local_id: u64,
 }
 
+/// A larger preview of a list item.
 #[derive(Debug, Clone)]
+pub enum Preview {
+    Markdown(String),
+    Code {
+        code: String,
+        /// Language to highlight the code with, e.g. `"rust"`.
+        language: Option<String>,
+    },
+    /// Absolute path to an image file.
+    Image(PathBuf),
+    /// A table of key/value pairs, in order.
+    Metadata(Vec<(String, String)>),
+}
+
+impl Preview {
+    pub(crate) fn from_proto(proto: proto::Preview) -> Option<Self> {
+        use proto::preview::Content;
+
+        Some(match proto.content? {
+            Content::Markdown(markdown) => Self::Markdown(markdown),
+            Content::Code(proto::CodeBlock { code, language }) => Self::Code { code, language },
+            Content::ImagePath(path) => Self::Image(PathBuf::from(path)),
+            Content::Metadata(metadata) => Self::Metadata(
+                metadata
+                    .entries
+                    .into_iter()
+                    .map(|entry| (entry.key, entry.value))
+                    .collect(),
+            ),
+        })
+    }
+}
+
+#[derive(Clone)]
 pub enum Icon {
+    /// A named icon, taken from the system.
     Name(String),
+    /// Just some text.
     Text(String),
+    /// Absolute path to an image file.
+    File(PathBuf),
+    /// An image encoded inline, at most [`Icon::MAX_IMAGE_BYTES`] long.
+    Image {
+        data: Vec<u8>,
+        /// MIME type of the data, e.g. `"image/png"`.
+        mime_type: String,
+    },
 }
 
 impl Icon {
+    /// Maximum size of an inline image.
+    ///
+    /// Larger images are ignored, as they need to be sent to the frontend
+    /// on every list update.
+    pub const MAX_IMAGE_BYTES: usize = 256 * 1024;
+
+    /// Maximum total size of the inline images in a list, including the
+    /// images of accessories.
+    ///
+    /// Images after this is reached are ignored.
+    pub const MAX_LIST_IMAGE_BYTES: usize = 4 * 1024 * 1024;
+
     pub(crate) fn from_proto(proto: proto::list_item::Icon) -> Self {
         use proto::list_item::Icon as Proto;
         match proto {
             Proto::Name(name) => Self::Name(name),
             Proto::Text(text) => Self::Text(text),
+            Proto::File(path) => Self::File(PathBuf::from(path)),
+            Proto::Image(proto::Image { data, mime_type }) => Self::Image { data, mime_type },
+        }
+    }
+
+    /// Checks that an icon from a plugin can be shown.
+    fn validate_proto(proto: &proto::list_item::Icon) -> Result<(), String> {
+        use proto::list_item::Icon as Proto;
+        match proto {
+            Proto::File(path) if !Path::new(path).is_absolute() => {
+                Err(format!("file path {path:?} is not absolute"))
+            }
+            Proto::Image(image) if image.data.len() > Self::MAX_IMAGE_BYTES => Err(format!(
+                "image is {} bytes, larger than the maximum of {} bytes",
+                image.data.len(),
+                Self::MAX_IMAGE_BYTES
+            )),
+            Proto::Image(image) if !image.mime_type.starts_with("image/") => {
+                Err(format!("{:?} is not an image MIME type", image.mime_type))
+            }
+            Proto::Name(_) | Proto::Text(_) | Proto::File(_) | Proto::Image(_) => Ok(()),
+        }
+    }
+}
+
+impl fmt::Debug for Icon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Name(name) => f.debug_tuple("Name").field(name).finish(),
+            Self::Text(text) => f.debug_tuple("Text").field(text).finish(),
+            Self::File(path) => f.debug_tuple("File").field(path).finish(),
+            Self::Image { data, mime_type } => f
+                .debug_struct("Image")
+                .field("data", &format!("{} bytes", data.len()))
+                .field("mime_type", mime_type)
+                .finish(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use covey_config::manifest::PluginManifest;
+
+    use super::{drop_images_over_budget, Accessory, Icon, ListItem};
+    use crate::{proto, Plugin};
+
+    fn plugin() -> Plugin {
+        Plugin::with_manifest(
+            toml::from_str(
+                r#"
+                id = "test"
+                prefix = "t "
+                "#,
+            )
+            .unwrap(),
+            PluginManifest::try_from_toml(r#"name = "Test""#).unwrap(),
+        )
+    }
+
+    fn image(bytes: usize) -> proto::Image {
+        proto::Image {
+            data: vec![0; bytes],
+            mime_type: "image/png".to_owned(),
+        }
+    }
+
+    fn item(
+        plugin: &Plugin,
+        icon: proto::list_item::Icon,
+        accessories: Vec<proto::Accessory>,
+    ) -> ListItem {
+        ListItem::new(
+            plugin.clone(),
+            proto::ListItem {
+                title: "item".to_owned(),
+                icon: Some(icon),
+                accessories,
+                ..proto::ListItem::default()
+            },
+        )
+    }
+
+    fn image_accessory(bytes: usize) -> proto::Accessory {
+        proto::Accessory {
+            kind: Some(proto::accessory::Kind::IconText(proto::IconText {
+                icon: Some(proto::icon_text::Icon::Image(image(bytes))),
+                text: "accessory".to_owned(),
+            })),
+        }
+    }
+
+    fn has_image(item: &ListItem) -> bool {
+        matches!(item.icon(), Some(Icon::Image { .. }))
+    }
+
+    #[test]
+    fn images_after_the_budget_are_dropped() {
+        use proto::list_item::Icon as Proto;
+
+        let plugin = plugin();
+        let mut items = vec![
+            item(&plugin, Proto::Image(image(40)), vec![image_accessory(40)]),
+            item(&plugin, Proto::Name("firefox".to_owned()), vec![]),
+            item(&plugin, Proto::Image(image(40)), vec![]),
+            item(&plugin, Proto::Image(image(10)), vec![image_accessory(10)]),
+        ];
+        drop_images_over_budget(&mut items, 100, &plugin);
+
+        assert!(has_image(&items[0]));
+        assert!(matches!(
+            items[0].accessories(),
+            [Accessory::IconText { .. }]
+        ));
+        assert!(matches!(items[1].icon(), Some(Icon::Name(_))));
+        assert!(!has_image(&items[2]));
+        assert!(
+            !has_image(&items[3]),
+            "later images are dropped even if they are small enough"
+        );
+        assert!(matches!(
+            items[3].accessories(),
+            [Accessory::Text(text)] if text == "accessory"
+        ));
+    }
+
+    #[test]
+    fn reserved_dynamic_hotkeys_are_dropped() {
+        let command = |id: &str, hotkey: &str| proto::DynamicCommand {
+            id: id.to_owned(),
+            title: id.to_owned(),
+            hotkey: Some(hotkey.to_owned()),
+        };
+        let item = ListItem::new(
+            plugin(),
+            proto::ListItem {
+                title: "item".to_owned(),
+                dynamic_commands: vec![command("close", "escape"), command("open", "ctrl+o")],
+                ..proto::ListItem::default()
+            },
+        );
+
+        let hotkeys: Vec<_> = item
+            .dynamic_commands()
+            .iter()
+            .map(|command| command.hotkey.as_ref().map(ToString::to_string))
+            .collect();
+        assert_eq!(hotkeys, [None, Some("Ctrl+O".to_owned())]);
+    }
+}
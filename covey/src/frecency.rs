@@ -0,0 +1,179 @@
+//! Ranking results of different plugins by which ones the user activated
+//! before.
+//!
+//! Activations are stored in [`DATA_DIR`]/activations.json.
+
+use std::{
+    path::PathBuf,
+    sync::LazyLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use covey_config::{config::FrecencyConfig, keyed_list::Id};
+use serde::{Deserialize, Serialize};
+
+use crate::DATA_DIR;
+
+pub(crate) static ACTIVATIONS_PATH: LazyLock<PathBuf> =
+    LazyLock::new(|| DATA_DIR.join("activations.json"));
+
+/// Most activations that are kept.
+///
+/// The oldest activations are forgotten first.
+const MAX_ACTIVATIONS: usize = 1000;
+
+/// Every list item that was activated, oldest first.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub(crate) struct Activations {
+    entries: Vec<Activation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct Activation {
+    /// The query without the plugin's prefix.
+    pub query: String,
+    pub plugin: Id,
+    /// Identifies the list item across queries.
+    pub item: String,
+    /// Name of the command that was activated.
+    pub command: String,
+    /// Seconds since the unix epoch.
+    pub time: u64,
+}
+
+impl Activations {
+    pub fn record(&mut self, activation: Activation) {
+        self.entries.push(activation);
+        if self.entries.len() > MAX_ACTIVATIONS {
+            let excess = self.entries.len() - MAX_ACTIVATIONS;
+            self.entries.drain(..excess);
+        }
+    }
+
+    /// Score of a list item, used to order it among the results of other
+    /// plugins.
+    ///
+    /// `position` is the index of the item in its plugin's list, which
+    /// is how relevant the plugin thinks it is. Every activation of the
+    /// item adds to this, with older activations counting less. Only
+    /// activations with a query starting with `query` count, so that what
+    /// the user picked while typing the same thing is preferred.
+    ///
+    /// The whole score is multiplied by the plugin's boost.
+    pub fn score(
+        &self,
+        config: &FrecencyConfig,
+        now: u64,
+        query: &str,
+        plugin: &Id,
+        item: &str,
+        position: usize,
+    ) -> f64 {
+        let half_life_secs = config.half_life_days * 24.0 * 60.0 * 60.0;
+        #[expect(clippy::cast_precision_loss, reason = "precision isn't needed")]
+        let frecency: f64 = self
+            .entries
+            .iter()
+            .filter(|activation| {
+                activation.plugin == *plugin
+                    && activation.item == item
+                    && activation.query.starts_with(query)
+            })
+            .map(|activation| {
+                let age = now.saturating_sub(activation.time) as f64;
+                0.5_f64.powf(age / half_life_secs)
+            })
+            .sum();
+        #[expect(clippy::cast_precision_loss, reason = "precision isn't needed")]
+        let relevance = 1.0 / (position as f64 + 1.0);
+
+        (relevance + frecency) * config.boost(plugin)
+    }
+}
+
+/// Seconds since the unix epoch.
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use covey_config::{config::FrecencyConfig, keyed_list::Id};
+
+    use super::{Activation, Activations, MAX_ACTIVATIONS};
+
+    const DAY: u64 = 24 * 60 * 60;
+    const NOW: u64 = 100 * DAY;
+
+    fn activation(query: &str, plugin: &str, item: &str, time: u64) -> Activation {
+        Activation {
+            query: query.to_owned(),
+            plugin: Id::new(plugin),
+            item: item.to_owned(),
+            command: "open".to_owned(),
+            time,
+        }
+    }
+
+    fn score(
+        activations: &Activations,
+        config: &FrecencyConfig,
+        query: &str,
+        plugin: &str,
+        item: &str,
+    ) -> f64 {
+        activations.score(config, NOW, query, &Id::new(plugin), item, 0)
+    }
+
+    #[test]
+    fn activations_decay_by_half_life() {
+        let mut activations = Activations::default();
+        activations.record(activation("fire", "apps", "Firefox", NOW));
+        activations.record(activation("fire", "files", "fire.txt", NOW - 7 * DAY));
+        let config = FrecencyConfig::default();
+
+        assert!((score(&activations, &config, "fire", "apps", "Firefox") - 2.0).abs() < 1e-9);
+        assert!((score(&activations, &config, "fire", "files", "fire.txt") - 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn only_activations_continuing_the_query_count() {
+        let mut activations = Activations::default();
+        activations.record(activation("fire", "apps", "Firefox", NOW));
+        let config = FrecencyConfig::default();
+
+        assert!(score(&activations, &config, "fi", "apps", "Firefox") > 1.0);
+        assert!((score(&activations, &config, "firm", "apps", "Firefox") - 1.0).abs() < 1e-9);
+        assert!((score(&activations, &config, "fire", "files", "Firefox") - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn plugin_boosts_multiply_scores() {
+        let activations = Activations::default();
+        let config = FrecencyConfig {
+            plugin_boosts: HashMap::from([(Id::new("apps"), 3.0)]),
+            ..FrecencyConfig::default()
+        };
+
+        assert!((score(&activations, &config, "", "apps", "Firefox") - 3.0).abs() < 1e-9);
+        assert!((score(&activations, &config, "", "files", "fire.txt") - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn oldest_activations_are_forgotten() {
+        let mut activations = Activations::default();
+        activations.record(activation("", "apps", "Old", 0));
+        for i in 0..MAX_ACTIVATIONS {
+            activations.record(activation("", "apps", &i.to_string(), NOW));
+        }
+
+        assert_eq!(activations.entries.len(), MAX_ACTIVATIONS);
+        assert!(activations.entries.iter().all(|a| a.item != "Old"));
+    }
+}
@@ -0,0 +1,223 @@
+//! Queries that the user has submitted, so they can be recalled later.
+//!
+//! History is stored in [`DATA_DIR`]/history.json.
+
+use std::{path::PathBuf, sync::LazyLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::DATA_DIR;
+
+pub(crate) static HISTORY_PATH: LazyLock<PathBuf> = LazyLock::new(|| DATA_DIR.join("history.json"));
+
+/// Most queries that are kept for each plugin prefix.
+///
+/// The oldest queries are forgotten first.
+const MAX_QUERIES_PER_PREFIX: usize = 100;
+
+/// Submitted queries of every plugin prefix.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct History {
+    /// Oldest first. The same query is never in here twice.
+    entries: Vec<Entry>,
+    /// Where the user is while going through the history.
+    #[serde(skip)]
+    cursor: Option<Cursor>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Entry {
+    prefix: String,
+    /// The query without the prefix.
+    query: String,
+}
+
+impl Entry {
+    fn input(&self) -> String {
+        format!("{}{}", self.prefix, self.query)
+    }
+}
+
+#[derive(Debug)]
+struct Cursor {
+    prefix: String,
+    /// Index of the entry that is being shown.
+    index: usize,
+    /// The input that was set to that entry.
+    shown: String,
+    /// The input before going through the history, which is restored
+    /// after going past the newest entry.
+    draft: String,
+}
+
+impl History {
+    /// Adds a query as the newest one of its prefix.
+    ///
+    /// Returns whether the history changed. Empty queries are not added.
+    pub fn record(&mut self, prefix: &str, query: &str) -> bool {
+        if query.trim().is_empty() {
+            return false;
+        }
+        let entry = Entry {
+            prefix: prefix.to_owned(),
+            query: query.to_owned(),
+        };
+        if self.entries.last() == Some(&entry) {
+            return false;
+        }
+
+        self.entries.retain(|other| *other != entry);
+        self.entries.push(entry);
+
+        let same_prefix = self.entries.iter().filter(|e| e.prefix == prefix).count();
+        if same_prefix > MAX_QUERIES_PER_PREFIX {
+            let oldest = self
+                .entries
+                .iter()
+                .position(|e| e.prefix == prefix)
+                .expect("there are entries with the prefix");
+            self.entries.remove(oldest);
+        }
+
+        // indices of the cursor may have moved
+        self.cursor = None;
+        true
+    }
+
+    /// Goes to the previous query with the same prefix as `input`.
+    ///
+    /// `input` is the current input, which starts with `prefix`. Going
+    /// back starts again from the newest query if `input` was changed
+    /// since the last call to this or [`History::next`].
+    ///
+    /// Returns the input to show, or [`None`] if there are no older
+    /// queries.
+    pub fn previous(&mut self, prefix: &str, input: &str) -> Option<String> {
+        let end = match &self.cursor {
+            Some(cursor) if cursor.prefix == prefix && cursor.shown == input => cursor.index,
+            _ => self.entries.len(),
+        };
+        let index = self.entries[..end]
+            .iter()
+            .rposition(|entry| entry.prefix == prefix)?;
+        Some(self.go_to(index, input))
+    }
+
+    /// Goes to the next query with the same prefix as `input`, undoing
+    /// [`History::previous`].
+    ///
+    /// Returns the input to show, or [`None`] if `input` isn't from the
+    /// history. After the newest query, the input from before going
+    /// through the history is returned.
+    pub fn next(&mut self, prefix: &str, input: &str) -> Option<String> {
+        let cursor = self
+            .cursor
+            .as_ref()
+            .filter(|cursor| cursor.prefix == prefix && cursor.shown == input)?;
+        let start = cursor.index + 1;
+        match self.entries[start..]
+            .iter()
+            .position(|entry| entry.prefix == prefix)
+        {
+            Some(offset) => Some(self.go_to(start + offset, input)),
+            None => self.cursor.take().map(|cursor| cursor.draft),
+        }
+    }
+
+    /// Moves the cursor to the entry at `index`, returning its input.
+    fn go_to(&mut self, index: usize, input: &str) -> String {
+        let entry = &self.entries[index];
+        let shown = entry.input();
+        let draft = match self.cursor.take() {
+            Some(cursor) if cursor.shown == input => cursor.draft,
+            _ => input.to_owned(),
+        };
+        self.cursor = Some(Cursor {
+            prefix: entry.prefix.clone(),
+            index,
+            shown: shown.clone(),
+            draft,
+        });
+        shown
+    }
+
+    /// Inputs of every query containing `search`, newest first.
+    ///
+    /// Case is ignored, and the prefix is included in what is searched.
+    pub fn search(&self, search: &str) -> Vec<String> {
+        let search = search.to_lowercase();
+        self.entries
+            .iter()
+            .rev()
+            .map(Entry::input)
+            .filter(|input| input.to_lowercase().contains(&search))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{History, MAX_QUERIES_PER_PREFIX};
+
+    fn history(entries: &[(&str, &str)]) -> History {
+        let mut history = History::default();
+        for (prefix, query) in entries {
+            history.record(prefix, query);
+        }
+        history
+    }
+
+    #[test]
+    fn recording_moves_duplicates_to_newest() {
+        let history = history(&[("=", "1+1"), ("ssh ", "host"), ("=", "1+1"), ("=", "")]);
+
+        assert_eq!(history.search(""), ["=1+1", "ssh host"]);
+    }
+
+    #[test]
+    fn oldest_queries_of_a_prefix_are_forgotten() {
+        let mut history = history(&[("ssh ", "host")]);
+        for i in 0..=MAX_QUERIES_PER_PREFIX {
+            history.record("=", &i.to_string());
+        }
+
+        let results = history.search("");
+        assert_eq!(results.len(), MAX_QUERIES_PER_PREFIX + 1);
+        assert!(!results.contains(&"=0".to_owned()));
+        assert!(results.contains(&"=1".to_owned()));
+        assert!(results.contains(&"ssh host".to_owned()));
+    }
+
+    #[test]
+    fn navigates_queries_of_the_same_prefix() {
+        let mut history = history(&[("=", "1+1"), ("ssh ", "host"), ("=", "2*3")]);
+
+        assert_eq!(history.previous("=", "=4").as_deref(), Some("=2*3"));
+        assert_eq!(history.previous("=", "=2*3").as_deref(), Some("=1+1"));
+        assert_eq!(history.previous("=", "=1+1"), None);
+        assert_eq!(history.next("=", "=1+1").as_deref(), Some("=2*3"));
+        assert_eq!(
+            history.next("=", "=2*3").as_deref(),
+            Some("=4"),
+            "restores the input from before navigating"
+        );
+        assert_eq!(history.next("=", "=4"), None);
+    }
+
+    #[test]
+    fn editing_the_input_restarts_navigation() {
+        let mut history = history(&[("=", "1+1"), ("=", "2*3")]);
+
+        assert_eq!(history.previous("=", "=").as_deref(), Some("=2*3"));
+        assert_eq!(history.next("=", "=2*3 + 1"), None);
+        assert_eq!(history.previous("=", "=2*3 + 1").as_deref(), Some("=2*3"));
+    }
+
+    #[test]
+    fn search_ignores_case() {
+        let history = history(&[("ssh ", "Server"), ("ssh ", "laptop")]);
+
+        assert_eq!(history.search("server"), ["ssh Server"]);
+        assert_eq!(history.search("SSH"), ["ssh laptop", "ssh Server"]);
+    }
+}
@@ -1,21 +1,35 @@
 use std::{
+    collections::HashMap,
     fs,
     future::Future,
     io::{Read as _, Write as _},
     sync::Arc,
+    time::Instant,
 };
 
 use color_eyre::eyre::{bail, eyre, Context, Result};
 use covey_config::{
     config::GlobalConfig,
+    conflicts::{self, HotkeyConflict},
+    hotkey::{KeyPress, SequenceMatch, SequenceMatcher},
     keyed_list::{Id, KeyedList},
+    manifest::{Command, ValidationError},
 };
+use futures::future::{self, Either};
 use parking_lot::Mutex;
+use tokio::process::Child;
 use tracing::{debug, error, info, warn};
 
 use crate::{
-    event::{Action, ListItemId, PluginEvent},
-    Frontend, Plugin, CONFIG_PATH,
+    commands::{self, HotkeyTarget},
+    event::{self, Action, CapturedCommand, ListItemId, PluginEvent, Program},
+    frecency::{self, Activation, Activations, ACTIVATIONS_PATH},
+    history::{History, HISTORY_PATH},
+    icons::IconResolver,
+    json_file::{self, DelayedFile},
+    spawn::Env,
+    Confirmation, Frontend, Input, List, ListItem, Page, ParameterRequest, Plugin, Preview,
+    CONFIG_PATH,
 };
 
 struct HostInner {
@@ -24,6 +38,72 @@ struct HostInner {
     activated_actions: u64,
     fe: Box<dyn Frontend>,
     config: GlobalConfig,
+    /// Stack of pages opened by plugins, with the most recent page last.
+    pages: Vec<PageEntry>,
+    /// The most recent input that was queried.
+    last_query: String,
+    /// The input that the list that is shown is the result of, or
+    /// [`None`] if its plugin asked for it to be left out of the history.
+    list_query: Option<String>,
+    /// Items in the list that is shown, by plugin and local ID.
+    list_items: HashMap<(Id, u64), ListItem>,
+    /// Plugin of the list that is shown.
+    list_plugin: Option<Id>,
+    history: History,
+    history_file: DelayedFile,
+    activations: Activations,
+    activations_file: DelayedFile,
+    /// The latest confirmation sent to the frontend that hasn't been
+    /// responded to yet, with it's ID.
+    pending_confirmation: Option<(u64, PendingConfirmation)>,
+    /// Number of confirmations that have been requested.
+    confirmations: u64,
+    /// The latest parameter request sent to the frontend that hasn't been
+    /// submitted yet, with it's ID.
+    pending_parameters: Option<(u64, ListItemId, Command)>,
+    /// Number of parameter requests that have been made.
+    parameter_requests: u64,
+    /// Keys pressed so far of an unfinished hotkey sequence.
+    hotkeys: SequenceMatcher,
+    /// Hotkeys in the config that hide each other.
+    hotkey_conflicts: Vec<HotkeyConflict>,
+}
+
+/// Something that will run once the user confirms it.
+#[derive(Debug)]
+enum PendingConfirmation {
+    Activation {
+        item: ListItemId,
+        command_name: String,
+        /// JSON object of the command's parameters, if it has any.
+        parameters: Option<String>,
+    },
+    Actions {
+        plugin: Plugin,
+        actions: Vec<Action>,
+    },
+}
+
+/// Work left over after handling an event that needs to be awaited.
+#[derive(Default)]
+struct FollowUp {
+    /// Another string that should be queried.
+    query: Option<String>,
+    /// Commands that are still running.
+    captures: Vec<RunningCapture>,
+}
+
+/// A command run with [`Action::RunCaptured`] that hasn't finished yet.
+struct RunningCapture {
+    plugin: Plugin,
+    command: CapturedCommand,
+    child: Child,
+}
+
+struct PageEntry {
+    page: Page,
+    /// Input before this page was opened, restored when it is closed.
+    previous_input: String,
 }
 
 /// Main public API for interacting with covey.
@@ -34,6 +114,11 @@ struct HostInner {
 #[derive(Clone)]
 pub struct Host {
     inner: Arc<Mutex<HostInner>>,
+    /// Resolver with the configured icon themes.
+    ///
+    /// This is outside of `inner` so that the frontend can resolve icons
+    /// while the host is calling it.
+    icons: Arc<Mutex<Arc<IconResolver>>>,
 }
 
 impl Host {
@@ -53,19 +138,75 @@ impl Host {
         debug!("read config:\n{s}");
 
         let global_config: GlobalConfig = toml::from_str(&s)?;
+
+        let history = json_file::read(&HISTORY_PATH).unwrap_or_else(|e| {
+            error!("error reading history from {:?}: {e:#}", &*HISTORY_PATH);
+            History::default()
+        });
+        let activations = json_file::read(&ACTIVATIONS_PATH).unwrap_or_else(|e| {
+            error!(
+                "error reading activations from {:?}: {e:#}",
+                &*ACTIVATIONS_PATH
+            );
+            Activations::default()
+        });
+
+        Ok(Self::with_config(fe, global_config, history, activations))
+    }
+
+    /// Creates a host from a config that has already been read.
+    fn with_config(
+        fe: impl Frontend,
+        global_config: GlobalConfig,
+        history: History,
+        activations: Activations,
+    ) -> Self {
         let plugins = Self::load_plugins(&global_config);
 
         info!("found plugins: {plugins:?}");
 
-        Ok(Self {
+        let hotkey_conflicts = Self::find_hotkey_conflicts(&global_config, &plugins);
+
+        let icons = Arc::new(IconResolver::new(global_config.app.icon_themes.clone()));
+
+        Self {
             inner: Arc::new(Mutex::new(HostInner {
                 plugins,
                 dispatched_actions: 0,
                 activated_actions: 0,
                 fe: Box::new(fe),
                 config: global_config,
+                pages: Vec::new(),
+                last_query: String::new(),
+                list_query: None,
+                list_items: HashMap::new(),
+                list_plugin: None,
+                history,
+                history_file: DelayedFile::new(&HISTORY_PATH),
+                activations,
+                activations_file: DelayedFile::new(&ACTIVATIONS_PATH),
+                pending_confirmation: None,
+                confirmations: 0,
+                pending_parameters: None,
+                parameter_requests: 0,
+                hotkeys: SequenceMatcher::default(),
+                hotkey_conflicts,
             })),
-        })
+            icons: Arc::new(Mutex::new(icons)),
+        }
+    }
+
+    /// Finds hotkeys that can never be activated, logging each of them.
+    fn find_hotkey_conflicts(
+        config: &GlobalConfig,
+        plugins: &KeyedList<Plugin>,
+    ) -> Vec<HotkeyConflict> {
+        let conflicts =
+            conflicts::find_conflicts(config, |id| plugins.get(id.as_str()).map(Plugin::manifest));
+        for conflict in &conflicts {
+            warn!("hotkey conflict: {conflict}");
+        }
+        conflicts
     }
 
     /// Reads the manifests of every plugin listed in the config.
@@ -113,6 +254,15 @@ impl Host {
         }
     }
 
+    /// Activates a command on a list item.
+    ///
+    /// If the command has parameters, [`Frontend::collect_parameters`] is
+    /// called and the command is only activated once they are submitted
+    /// with [`Host::submit_parameters`].
+    ///
+    /// If the command requires confirmation, [`Frontend::confirm`] is called
+    /// and the command is only activated once it is accepted with
+    /// [`Host::confirm`].
     #[tracing::instrument(skip(self))]
     pub fn activate(
         &self,
@@ -121,50 +271,458 @@ impl Host {
     ) -> impl Future<Output = ()> + use<> {
         debug!("activating {item:?}");
 
+        let command = item
+            .plugin
+            .manifest()
+            .commands
+            .get(&command_name)
+            .filter(|command| !command.parameters.is_empty())
+            .cloned();
+        if let Some(command) = command {
+            self.inner.lock().request_parameters(item, command);
+            return Either::Left(future::ready(()));
+        }
+
+        Either::Right(self.activate_with_parameters(item, command_name, None))
+    }
+
+    /// Presses a key while an item in the list that is shown is selected,
+    /// or while nothing is selected if `item` is [`None`].
+    ///
+    /// Once the keys pressed so far are a command's whole
+    /// [hotkey sequence](covey_config::hotkey::HotkeySequence), that
+    /// command is activated on the item. The
+    /// [reload hotkey](covey_config::config::AppConfig::reload_hotkey)
+    /// reloads the plugin of the list and queries the input again. If they
+    /// only start a sequence, this returns [`SequenceMatch::Pending`] and
+    /// [`Host::pending_hotkeys`] has them, until the rest is pressed or the
+    /// timeout passes.
+    ///
+    /// If several commands have the same hotkey, the first one in
+    /// [`ListItem::commands`] is activated.
+    pub fn press_hotkey(
+        &self,
+        item: Option<&ListItemId>,
+        press: KeyPress,
+    ) -> SequenceMatch<impl Future<Output = ()> + use<>> {
+        let command = {
+            let mut inner = self.inner.lock();
+            let inner = &mut *inner;
+            let item_commands = item
+                .and_then(|item| {
+                    let list_item = inner
+                        .list_items
+                        .get(&(item.plugin.id().clone(), item.local_id));
+                    if list_item.is_none() {
+                        warn!("pressed a hotkey on {item:?}, which isn't in the list");
+                    }
+                    list_item
+                })
+                .map(ListItem::commands)
+                .unwrap_or_default();
+            commands::press(
+                &mut inner.hotkeys,
+                &inner.config.app.reload_hotkey,
+                &item_commands,
+                press,
+                Instant::now(),
+            )
+            .map(|target| match target {
+                HotkeyTarget::Reload => None,
+                HotkeyTarget::Command(command) => Some(command.id.clone()),
+            })
+        };
+        command.map(|command| match command.zip(item) {
+            Some((command, item)) => Either::Left(self.activate(item.clone(), command)),
+            None => Either::Right(self.reload_list_plugin()),
+        })
+    }
+
+    /// Keys pressed with [`Host::press_hotkey`] that start a hotkey
+    /// sequence, but haven't finished it yet.
+    ///
+    /// This is empty once the sequence has timed out.
+    pub fn pending_hotkeys(&self) -> Vec<KeyPress> {
+        self.inner.lock().hotkeys.pending(Instant::now()).to_vec()
+    }
+
+    /// Reloads the plugin of the list that is shown, then queries the
+    /// input again.
+    fn reload_list_plugin(&self) -> impl Future<Output = ()> + use<> {
+        let (plugin, input) = {
+            let inner = self.inner.lock();
+            (inner.list_plugin.clone(), inner.last_query.clone())
+        };
+        if let Some(plugin) = plugin {
+            self.reload_plugin(&plugin);
+        }
+        self.query(input)
+    }
+
+    /// Responds to a request from [`Frontend::collect_parameters`].
+    ///
+    /// `values` should be [`None`] if the user cancelled, in which case the
+    /// command is not activated.
+    ///
+    /// # Errors
+    /// Returns an error if the values don't match the command's parameters.
+    /// The request stays pending, so the frontend can show the error and
+    /// let the user try again.
+    #[tracing::instrument(skip(self))]
+    pub fn submit_parameters(
+        &self,
+        id: u64,
+        values: Option<serde_json::Map<String, serde_json::Value>>,
+    ) -> Result<impl Future<Output = ()> + use<>, ValidationError> {
+        let mut inner = self.inner.lock();
+        let pending = match inner.pending_parameters.take() {
+            Some((pending_id, item, command)) if pending_id == id => (item, command),
+            other => {
+                warn!("parameter request {id} is not pending");
+                inner.pending_parameters = other;
+                return Ok(Either::Left(future::ready(())));
+            }
+        };
+        let Some(values) = values else {
+            return Ok(Either::Left(future::ready(())));
+        };
+
+        let (item, command) = pending;
+        if let Err(e) = command.validate_parameters(&values) {
+            inner.pending_parameters = Some((id, item, command));
+            return Err(e);
+        }
+        drop(inner);
+
+        let parameters = serde_json::Value::Object(values).to_string();
+        Ok(Either::Right(self.activate_with_parameters(
+            item,
+            command.id.as_str().to_owned(),
+            Some(parameters),
+        )))
+    }
+
+    /// Activates a command, asking for confirmation first if needed.
+    fn activate_with_parameters(
+        &self,
+        item: ListItemId,
+        command_name: String,
+        parameters: Option<String>,
+    ) -> impl Future<Output = ()> + use<> {
+        if item.plugin.requires_confirmation(&command_name) {
+            let title = item
+                .plugin
+                .manifest()
+                .commands
+                .get(&command_name)
+                .map_or(command_name.as_str(), |command| command.title.as_str());
+            let message = format!("Run \"{title}\"?");
+            let plugin = item.plugin.clone();
+
+            self.inner.lock().request_confirmation(
+                plugin,
+                message,
+                PendingConfirmation::Activation {
+                    item,
+                    command_name,
+                    parameters,
+                },
+            );
+            return Either::Left(future::ready(()));
+        }
+
+        Either::Right(self.activate_confirmed(item, command_name, parameters))
+    }
+
+    /// Responds to a confirmation requested by [`Frontend::confirm`].
+    ///
+    /// If `accepted` is true, the activation or actions that needed
+    /// confirmation are run. Does nothing if `id` is not the ID of the
+    /// most recent confirmation.
+    #[tracing::instrument(skip(self))]
+    pub fn confirm(&self, id: u64, accepted: bool) -> impl Future<Output = ()> + use<> {
+        let pending = {
+            let mut inner = self.inner.lock();
+            match inner.pending_confirmation.take() {
+                Some((pending_id, pending)) if pending_id == id => Some(pending),
+                other => {
+                    warn!("confirmation {id} is not pending");
+                    inner.pending_confirmation = other;
+                    None
+                }
+            }
+        };
+
+        let this = self.clone();
+        async move {
+            match pending.filter(|_| accepted) {
+                Some(PendingConfirmation::Activation {
+                    item,
+                    command_name,
+                    parameters,
+                }) => {
+                    this.activate_confirmed(item, command_name, parameters)
+                        .await;
+                }
+                Some(PendingConfirmation::Actions { plugin, actions }) => {
+                    this.handle_event(Ok(PluginEvent::Run { plugin, actions }))
+                        .await;
+                }
+                None => {}
+            }
+        }
+    }
+
+    fn activate_confirmed(
+        &self,
+        item: ListItemId,
+        command_name: String,
+        parameters: Option<String>,
+    ) -> impl Future<Output = ()> + use<> {
+        self.inner.lock().record_activation(&item, &command_name);
+
         self.make_event_future(async move {
             item.plugin
-                .activate(item.local_id, command_name)
+                .activate(item.local_id, command_name, parameters)
                 .await
-                .map(PluginEvent::Run)
+                .map(|actions| PluginEvent::Run {
+                    plugin: item.plugin,
+                    actions,
+                })
         })
     }
 
+    /// Fetches a larger preview of a list item.
+    ///
+    /// Returns [`None`] if the item has no preview. Previews are not sent
+    /// to the frontend, so the frontend should ignore previews of items
+    /// that are no longer selected.
+    #[tracing::instrument(skip(self))]
+    pub fn preview(
+        &self,
+        item: ListItemId,
+    ) -> impl Future<Output = Result<Option<Preview>>> + use<> {
+        async move { item.plugin.preview(item.local_id).await }
+    }
+
+    /// Closes the page that is currently open, restoring the input from
+    /// before it was opened.
+    ///
+    /// Returns [`None`] if there is no page open. Otherwise, returns a future
+    /// that queries the restored input.
+    #[tracing::instrument(skip(self))]
+    pub fn pop_page(&self) -> Option<impl Future<Output = ()> + use<>> {
+        let previous_input = {
+            let mut inner = self.inner.lock();
+            let entry = inner.pages.pop()?;
+            debug!("closing page {:?}", entry.page);
+
+            let top = inner.pages.last().map(|entry| entry.page.clone());
+            inner.fe.set_page(top);
+            inner.fe.set_input(Input::new(entry.previous_input.clone()));
+            entry.previous_input
+        };
+
+        Some(self.query(previous_input))
+    }
+
+    /// Replaces the input, as if the user typed it, and queries it.
+    ///
+    /// Any open pages are closed, so the input is queried on the main list.
+    #[tracing::instrument(skip(self))]
+    pub fn set_input(&self, input: Input) -> impl Future<Output = ()> + use<> {
+        {
+            let mut inner = self.inner.lock();
+            if !inner.pages.is_empty() {
+                inner.pages.clear();
+                inner.fe.set_page(None);
+            }
+            inner.fe.set_input(input.clone());
+        }
+
+        self.query(input.contents)
+    }
+
+    /// Replaces the input with the previous query that was submitted to
+    /// the plugin of the current input, and queries it.
+    ///
+    /// Calling this again goes further back, until the input is changed
+    /// some other way. Returns [`None`] if there are no older queries or a
+    /// page is open.
+    #[tracing::instrument(skip(self))]
+    pub fn history_previous(&self) -> Option<impl Future<Output = ()> + use<>> {
+        let input = self.inner.lock().navigate_history(History::previous)?;
+        Some(self.query(input))
+    }
+
+    /// Undoes [`Host::history_previous`], going to a newer query.
+    ///
+    /// After the newest query, the input from before going through the
+    /// history is restored. Returns [`None`] if the input isn't from the
+    /// history.
+    #[tracing::instrument(skip(self))]
+    pub fn history_next(&self) -> Option<impl Future<Output = ()> + use<>> {
+        let input = self.inner.lock().navigate_history(History::next)?;
+        Some(self.query(input))
+    }
+
+    /// Submitted queries of every plugin that contain `search`, ignoring
+    /// case, with the newest first.
+    ///
+    /// These include the plugin's prefix, so they can be set as the input.
+    pub fn search_history(&self, search: &str) -> Vec<String> {
+        self.inner.lock().history.search(search)
+    }
+
+    /// The page that is currently open, if any.
+    pub fn current_page(&self) -> Option<Page> {
+        self.inner
+            .lock()
+            .pages
+            .last()
+            .map(|entry| entry.page.clone())
+    }
+
     /// Calls a plugin with this input.
     #[tracing::instrument(skip(self))]
     pub fn query(&self, input: String) -> impl Future<Output = ()> + use<> {
         debug!("setting input to {input:?}");
-        let (plugins, this_action_index) = {
+        let (plugins, page, this_action_index) = {
             let mut inner = self.inner.lock();
             inner.dispatched_actions += 1;
+            inner.last_query.clone_from(&input);
 
-            (inner.plugins.clone(), inner.dispatched_actions)
+            (
+                inner.plugins.clone(),
+                inner.pages.last().map(|entry| entry.page.clone()),
+                inner.dispatched_actions,
+            )
         };
 
+        let this = self.clone();
         self.make_event_future(async move {
-            for plugin in plugins {
-                let Some(stripped) = input.strip_prefix(plugin.prefix()) else {
-                    continue;
-                };
-                debug!("querying plugin {plugin:?}");
-                let list = plugin.query(stripped).await?;
+            // an open page gets the entire input
+            if let Some(page) = page {
+                debug!("querying page {page:?}");
+                let list = page.plugin.query(&input, Some(page.local_id)).await?;
 
                 return Ok(PluginEvent::SetList {
                     list,
+                    query: input,
                     index: this_action_index,
                 });
             }
 
-            bail!("no plugin activated")
+            // every plugin with the same prefix as the first match gets
+            // the query, and their results are shown together
+            let Some(prefix) = plugins
+                .iter()
+                .map(Plugin::prefix)
+                .find(|prefix| input.starts_with(prefix))
+            else {
+                bail!("no plugin activated")
+            };
+            let stripped = &input[prefix.len()..];
+            let matching: Vec<_> = plugins
+                .iter()
+                .filter(|plugin| plugin.prefix() == prefix)
+                .collect();
+
+            debug!("querying plugins {matching:?}");
+            let results =
+                future::join_all(matching.iter().map(|plugin| plugin.query(stripped, None))).await;
+            let mut lists = Vec::with_capacity(results.len());
+            let mut last_error = None;
+            for (plugin, result) in matching.iter().zip(results) {
+                match result {
+                    Ok(list) => lists.push(list),
+                    Err(e) => {
+                        error!("error querying plugin {plugin:?}: {e:#}");
+                        last_error = Some(e);
+                    }
+                }
+            }
+            // a plugin that fails only shows an error if no plugin has results
+            let list = match lists.len() {
+                0 => return Err(last_error.expect("every plugin failed")),
+                1 => lists.remove(0),
+                _ => this.inner.lock().merge_lists(stripped, lists),
+            };
+
+            Ok(PluginEvent::SetList {
+                list,
+                query: input,
+                index: this_action_index,
+            })
         })
     }
 
     async fn handle_event(&self, event: Result<PluginEvent>) {
-        let chained_query = self.inner.lock().handle_event(event);
+        let FollowUp { query, captures } = self.inner.lock().handle_event(event);
 
-        if let Some(query) = chained_query {
-            // indirection needed to avoid infinitely sized future
-            Box::pin(self.query(query)).await;
+        // indirection needed to avoid infinitely sized future
+        let captures = future::join_all(
+            captures
+                .into_iter()
+                .map(|capture| Box::pin(self.wait_for_capture(capture))),
+        );
+        let chained_query = async {
+            if let Some(query) = query {
+                Box::pin(self.query(query)).await;
+            }
+        };
+
+        future::join(captures, chained_query).await;
+    }
+
+    /// Waits for a captured command to finish.
+    ///
+    /// If it failed, stderr is displayed as an error. Otherwise, the output
+    /// is sent back to the plugin if it asked for it.
+    async fn wait_for_capture(&self, capture: RunningCapture) {
+        let RunningCapture {
+            plugin,
+            command,
+            child,
+        } = capture;
+
+        let output = match child
+            .wait_with_output()
+            .await
+            .context(format!("failed to wait for command `{}`", command.program))
+        {
+            Ok(output) => output,
+            Err(e) => {
+                error!("Error running command: {e:#}");
+                self.inner
+                    .lock()
+                    .fe
+                    .display_error("Error running command", e);
+                return;
+            }
+        };
+
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+
+        if !output.status.success() {
+            let e = eyre!("{}", stderr.trim_end()).wrap_err(format!(
+                "command `{}` failed with {}",
+                command.program, output.status
+            ));
+            error!("Error running command: {e:#}");
+            self.inner.lock().fe.display_error("Command failed", e);
+            return;
         }
+
+        let Some(callback_id) = command.callback_id else {
+            return;
+        };
+        let event = plugin
+            .handle_output(callback_id, stdout, stderr)
+            .await
+            .map(|actions| PluginEvent::Run { plugin, actions });
+        self.handle_event(event).await;
     }
 
     /// Reloads all plugins with the new configuration.
@@ -175,6 +733,12 @@ impl Host {
         debug!("reloading");
         let mut inner = self.inner.lock();
         inner.plugins = Self::load_plugins(&config);
+        inner.hotkey_conflicts = Self::find_hotkey_conflicts(&config, &inner.plugins);
+        // icon themes may have changed, so the cache is invalid
+        *self.icons.lock() = Arc::new(IconResolver::new(config.app.icon_themes.clone()));
+        // pages may be from plugins that no longer exist
+        inner.pages.clear();
+        inner.fe.set_page(None);
         // TODO: spawn this in another task and handle errors properly
         Self::write_config(&config).expect("TODO");
         inner.config = config.clone();
@@ -217,14 +781,42 @@ impl Host {
             }
         });
 
-        self.inner.lock().plugins =
-            KeyedList::new(new_plugins).expect("new keyed list should have same keys");
+        let mut inner = self.inner.lock();
+        inner.plugins = KeyedList::new(new_plugins).expect("new keyed list should have same keys");
+        // the manifest may have changed
+        inner.hotkey_conflicts = Self::find_hotkey_conflicts(&inner.config, &inner.plugins);
+        // the reloaded plugin has lost all of it's pages
+        if let Some(index) = inner
+            .pages
+            .iter()
+            .position(|entry| entry.page.plugin.id() == plugin_id)
+        {
+            inner.pages.truncate(index);
+            let top = inner.pages.last().map(|entry| entry.page.clone());
+            inner.fe.set_page(top);
+        }
     }
 
     pub fn config(&self) -> GlobalConfig {
         self.inner.lock().config.clone()
     }
 
+    /// Hotkeys in the config that can never be activated, because another
+    /// hotkey is activated first or the launcher uses them.
+    ///
+    /// These are found when the config or a plugin is (re)loaded.
+    pub fn hotkey_conflicts(&self) -> Vec<HotkeyConflict> {
+        self.inner.lock().hotkey_conflicts.clone()
+    }
+
+    /// Resolves named icons with the configured icon themes.
+    ///
+    /// The resolver caches icons, and is replaced when the config is
+    /// reloaded.
+    pub fn icons(&self) -> Arc<IconResolver> {
+        Arc::clone(&self.icons.lock())
+    }
+
     /// Ordered set of all plugins.
     #[tracing::instrument(skip(self))]
     pub fn plugins(&self) -> KeyedList<Plugin> {
@@ -240,64 +832,392 @@ impl Host {
 }
 
 impl HostInner {
-    /// Optionally returns another string that should be queried.
+    /// Saves the current query in the history, and the activation for
+    /// ranking results.
+    ///
+    /// Nothing is saved unless the item is on a plugin's main list and the
+    /// plugin didn't mark the query as private. The query is the one that
+    /// the list is the result of, which may be older than the input.
+    fn record_activation(&mut self, item: &ListItemId, command_name: &str) {
+        if !self.pages.is_empty() {
+            return;
+        }
+        let plugin = &item.plugin;
+        let Some(query) = self
+            .list_query
+            .as_deref()
+            .and_then(|input| input.strip_prefix(plugin.prefix()))
+        else {
+            return;
+        };
+
+        if self.history.record(plugin.prefix(), query) {
+            self.history_file.schedule(&self.history);
+        }
+
+        let Some(list_item) = self.list_items.get(&(plugin.id().clone(), item.local_id)) else {
+            return;
+        };
+        self.activations.record(Activation {
+            query: query.to_owned(),
+            plugin: plugin.id().clone(),
+            item: list_item.key().to_owned(),
+            command: command_name.to_owned(),
+            time: frecency::now(),
+        });
+        self.activations_file.schedule(&self.activations);
+    }
+
+    /// Combines the lists of plugins with the same prefix into one.
+    ///
+    /// Items are ordered by [`Activations::score`], so that results the
+    /// user activated before for this query come first. Sections are
+    /// dropped, as items of a section may no longer be together.
+    fn merge_lists(&self, query: &str, lists: Vec<List>) -> List {
+        let config = &self.config.app.frecency;
+        let now = frecency::now();
+
+        let style = lists.iter().find_map(|list| list.style);
+        let private = lists.iter().any(|list| list.private);
+        let plugin = lists[0].plugin.clone();
+
+        let mut scored: Vec<_> = lists
+            .into_iter()
+            .flat_map(|list| list.items.into_iter().enumerate())
+            .map(|(position, item)| {
+                let score = self.activations.score(
+                    config,
+                    now,
+                    query,
+                    item.plugin().id(),
+                    item.key(),
+                    position,
+                );
+                (score, item)
+            })
+            .collect();
+        // sort reversed
+        scored.sort_by(|(s1, _), (s2, _)| s2.total_cmp(s1));
+
+        List {
+            items: scored.into_iter().map(|(_, item)| item).collect(),
+            sections: vec![],
+            style,
+            plugin,
+            private,
+        }
+    }
+
+    /// Moves through the history of the plugin that the current input
+    /// goes to, setting the input to the query that `navigate` returns.
+    fn navigate_history(
+        &mut self,
+        navigate: impl FnOnce(&mut History, &str, &str) -> Option<String>,
+    ) -> Option<String> {
+        // pages don't use the prefix, so they have no history
+        if !self.pages.is_empty() {
+            return None;
+        }
+        let plugin = self
+            .plugins
+            .iter()
+            .find(|plugin| self.last_query.starts_with(plugin.prefix()))?;
+        let input = navigate(&mut self.history, plugin.prefix(), &self.last_query)?;
+        self.fe.set_input(Input::new(input.clone()));
+        Some(input)
+    }
+
+    /// Environment for commands that don't specify any.
+    fn default_env(&self) -> Env<'_> {
+        Env {
+            cwd: None,
+            vars: None,
+            strip: &self.config.app.strip_env_vars,
+        }
+    }
+
+    fn command_env<'a>(&'a self, command: &'a event::Command) -> Env<'a> {
+        Env {
+            cwd: command.cwd.as_deref(),
+            vars: Some(&command.env),
+            strip: &self.config.app.strip_env_vars,
+        }
+    }
+
+    /// Asks the frontend for the parameters of a command.
+    ///
+    /// This replaces any previous request that hasn't been submitted.
+    fn request_parameters(&mut self, item: ListItemId, command: Command) {
+        self.parameter_requests += 1;
+        let id = self.parameter_requests;
+        let plugin = item.plugin.clone();
+        self.pending_parameters = Some((id, item, command.clone()));
+        self.fe.collect_parameters(ParameterRequest {
+            id,
+            plugin,
+            command,
+        });
+    }
+
+    /// Asks the frontend to confirm something.
+    ///
+    /// This replaces any previous confirmation that hasn't been
+    /// responded to.
+    fn request_confirmation(
+        &mut self,
+        plugin: Plugin,
+        message: String,
+        pending: PendingConfirmation,
+    ) {
+        self.confirmations += 1;
+        let id = self.confirmations;
+        self.pending_confirmation = Some((id, pending));
+        self.fe.confirm(Confirmation {
+            id,
+            plugin,
+            message,
+        });
+    }
+
     #[tracing::instrument(skip(self))]
-    fn handle_event(&mut self, event: Result<PluginEvent>) -> Option<String> {
+    fn handle_event(&mut self, event: Result<PluginEvent>) -> FollowUp {
         debug!("handling event");
+        let mut follow_up = FollowUp::default();
 
         match event {
-            Ok(PluginEvent::SetList { list, index }) => {
+            Ok(PluginEvent::SetList { list, query, index }) => {
                 if index <= self.activated_actions {
-                    return None;
+                    return follow_up;
                 }
                 self.activated_actions = index;
+                self.list_query = Some(query).filter(|_| !list.private);
+                self.list_items = list
+                    .items
+                    .iter()
+                    .map(|item| {
+                        let key = (item.plugin().id().clone(), item.id().local_id);
+                        (key, item.clone())
+                    })
+                    .collect();
+                self.list_plugin = Some(list.plugin.id().clone());
                 self.fe.set_list(list);
             }
-            Ok(PluginEvent::Run(actions)) => {
-                return actions
-                    .into_iter()
-                    .fold(None, |opt, action| self.handle_action(action).or(opt));
+            Ok(PluginEvent::Run { plugin, actions }) => {
+                for action in actions {
+                    if let Some(query) = self.handle_action(&plugin, action, &mut follow_up) {
+                        follow_up.query = Some(query);
+                    }
+                }
             }
             Err(e) => {
                 self.fe.display_error("Error in plugin", e);
             }
         }
 
-        None
+        follow_up
     }
 
     /// Optionally returns another string that should be queried.
-    #[tracing::instrument(skip(self))]
-    fn handle_action(&mut self, action: Action) -> Option<String> {
+    ///
+    /// Captured commands that are started are added to `follow_up`.
+    #[tracing::instrument(skip(self, follow_up))]
+    fn handle_action(
+        &mut self,
+        plugin: &Plugin,
+        action: Action,
+        follow_up: &mut FollowUp,
+    ) -> Option<String> {
         info!("handling action {action:?}");
 
         match action {
             Action::Close => self.fe.close(),
-            Action::RunCommand(cmd, args) => {
-                if let Err(e) = crate::spawn::free_null(&cmd, &args).context(format!(
-                    "failed to run command `{cmd} {args}`",
-                    args = args.join(" ")
-                )) {
+            Action::RunCommand(command) => {
+                let env = self.command_env(&command);
+                if let Err(e) = crate::spawn::detached(&command.cmd, &command.args, env)
+                    .context(format!("failed to run command `{command}`"))
+                {
                     error!("Error running command: {e:#}");
                     self.fe.display_error("Error running command", e);
                 }
             }
             Action::RunShell(str) => {
-                if let Err(e) = crate::spawn::free_null("sh", ["-c", &str])
+                if let Err(e) = crate::spawn::detached("sh", ["-c", &str], self.default_env())
                     .context(format!("failed to run command `{str}`"))
                 {
                     error!("Error running command: {e:#}");
                     self.fe.display_error("Error running command", e);
                 }
             }
+            Action::RunCaptured(command) => {
+                let child = match &command.program {
+                    Program::Command(cmd) => {
+                        crate::spawn::captured(&cmd.cmd, &cmd.args, self.command_env(cmd))
+                    }
+                    Program::Shell(str) => {
+                        crate::spawn::captured("sh", ["-c", str], self.default_env())
+                    }
+                }
+                .context(format!("failed to run command `{}`", command.program));
+
+                match child {
+                    Ok(child) => follow_up.captures.push(RunningCapture {
+                        plugin: plugin.clone(),
+                        command,
+                        child,
+                    }),
+                    Err(e) => {
+                        error!("Error running command: {e:#}");
+                        self.fe.display_error("Error running command", e);
+                    }
+                }
+            }
+            Action::RunInTerminal { command, hold } => {
+                let result = crate::terminal::command(
+                    self.config.app.terminal.as_deref(),
+                    command.cmd,
+                    command.args,
+                    hold,
+                )
+                .and_then(|(cmd, args)| {
+                    // the terminal passes these on to the command
+                    let env = Env {
+                        cwd: command.cwd.as_deref(),
+                        vars: Some(&command.env),
+                        ..self.default_env()
+                    };
+                    crate::spawn::detached(&cmd, &args, env).context(format!(
+                        "failed to open terminal `{cmd} {args}`",
+                        args = args.join(" ")
+                    ))
+                });
+                if let Err(e) = result {
+                    error!("Error opening terminal: {e:#}");
+                    self.fe.display_error("Error opening terminal", e);
+                }
+            }
             Action::Copy(str) => {
                 self.fe.copy(str);
             }
-            Action::SetInput(input) => {
+            Action::Paste(str) => {
+                self.fe.paste(str);
+            }
+            Action::SetInput(mut input) => {
+                // pages don't need the prefix
+                if self.pages.is_empty() {
+                    input.prefix_with(plugin.prefix());
+                }
                 self.fe.set_input(input.clone());
                 return Some(input.contents);
             }
+            Action::PushPage(page) => {
+                let input = page.input.clone();
+                self.pages.push(PageEntry {
+                    page: page.clone(),
+                    previous_input: std::mem::take(&mut self.last_query),
+                });
+                self.fe.set_page(Some(page));
+                self.fe.set_input(input.clone());
+                return Some(input.contents);
+            }
+            Action::Confirm { message, actions } => {
+                self.request_confirmation(
+                    plugin.clone(),
+                    message,
+                    PendingConfirmation::Actions {
+                        plugin: plugin.clone(),
+                        actions,
+                    },
+                );
+            }
+            Action::Notify(notification) => {
+                self.fe.notify(notification);
+            }
         }
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use covey_config::{config::GlobalConfig, manifest::PluginManifest};
+    use parking_lot::Mutex;
+
+    use super::{Host, PageEntry};
+    use crate::{
+        frecency::Activations, history::History, Frontend, Input, List, Notification, Page, Plugin,
+    };
+
+    /// Frontend that records the inputs and page titles it is given.
+    #[derive(Clone, Default)]
+    struct Recorder {
+        inputs: Arc<Mutex<Vec<String>>>,
+        pages: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    impl Frontend for Recorder {
+        fn close(&mut self) {}
+        fn copy(&mut self, _: String) {}
+        fn set_input(&mut self, input: Input) {
+            self.inputs.lock().push(input.contents);
+        }
+        fn set_list(&mut self, _: List) {}
+        fn set_page(&mut self, page: Option<Page>) {
+            self.pages.lock().push(page.and_then(|page| page.title));
+        }
+        fn notify(&mut self, _: Notification) {}
+        fn reload(&mut self, _: GlobalConfig) {}
+        fn display_error(&mut self, _: &str, _: color_eyre::eyre::Report) {}
+    }
+
+    fn page(plugin: &Plugin, title: &str) -> Page {
+        Page {
+            plugin: plugin.clone(),
+            local_id: 0,
+            title: Some(title.to_owned()),
+            placeholder: None,
+            input: Input::new(String::new()),
+        }
+    }
+
+    #[test]
+    fn popping_pages_restores_the_input_from_before_each_page() {
+        let fe = Recorder::default();
+        let host = Host::with_config(
+            fe.clone(),
+            toml::from_str("").unwrap(),
+            History::default(),
+            Activations::default(),
+        );
+        let plugin = Plugin::with_manifest(
+            toml::from_str(
+                r#"
+                id = "test"
+                prefix = "t "
+                "#,
+            )
+            .unwrap(),
+            PluginManifest::try_from_toml(r#"name = "Test""#).unwrap(),
+        );
+        {
+            let mut inner = host.inner.lock();
+            inner.pages.push(PageEntry {
+                page: page(&plugin, "First"),
+                previous_input: "t query".to_owned(),
+            });
+            inner.pages.push(PageEntry {
+                page: page(&plugin, "Second"),
+                previous_input: "first page input".to_owned(),
+            });
+        }
+
+        // the returned futures query the plugin, which isn't running
+        assert!(host.pop_page().is_some());
+        assert!(host.pop_page().is_some());
+        assert!(host.pop_page().is_none());
+
+        assert_eq!(*fe.inputs.lock(), ["first page input", "t query"]);
+        assert_eq!(*fe.pages.lock(), [Some("First".to_owned()), None]);
+    }
+}
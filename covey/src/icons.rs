@@ -0,0 +1,564 @@
+//! Resolving named icons from plugins into images.
+//!
+//! Named icons are looked up in the user's [`IconTheme`]s, in order.
+//! System themes follow the [freedesktop icon theme specification],
+//! and Iconify themes are read from JSON icon sets in [`ICONIFY_DIR`].
+//!
+//! [freedesktop icon theme specification]: https://specifications.freedesktop.org/icon-theme-spec/latest/
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock},
+};
+
+use covey_config::config::{IconTheme, IconThemeKind};
+use parking_lot::Mutex;
+use serde::Deserialize;
+
+use crate::DATA_DIR;
+
+/// Folder containing offline Iconify icon sets.
+///
+/// Each icon set should be a JSON file named after it's prefix, e.g.
+/// `ph.json`, in the format of the [`@iconify/json`] package.
+///
+/// [`@iconify/json`]: https://github.com/iconify/icon-sets
+pub static ICONIFY_DIR: LazyLock<PathBuf> =
+    LazyLock::new(|| DATA_DIR.join("icons").join("iconify"));
+
+/// Theme that every system theme falls back to.
+const FALLBACK_THEME: &str = "hicolor";
+
+/// File extensions of system icons, in order of preference.
+const EXTENSIONS: [&str; 3] = ["png", "svg", "xpm"];
+
+/// An image that a named icon resolved to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolvedIcon {
+    File(PathBuf),
+    /// An SVG document, from an Iconify icon set.
+    Svg(String),
+}
+
+/// Resolves named icons with a list of themes.
+///
+/// Results are cached, so the same resolver should be reused across
+/// queries.
+pub struct IconResolver {
+    themes: Vec<IconTheme>,
+    /// Directories that system themes are searched for in, most important
+    /// first.
+    base_dirs: Vec<PathBuf>,
+    /// Key is (name, size, scale).
+    cache: Mutex<HashMap<(String, u32, u32), Option<ResolvedIcon>>>,
+    system_themes: Mutex<HashMap<String, Option<Arc<SystemTheme>>>>,
+    iconify_sets: Mutex<HashMap<String, Option<Arc<IconifySet>>>>,
+}
+
+impl IconResolver {
+    pub fn new(themes: Vec<IconTheme>) -> Self {
+        Self::with_base_dirs(themes, base_dirs())
+    }
+
+    fn with_base_dirs(themes: Vec<IconTheme>, base_dirs: Vec<PathBuf>) -> Self {
+        Self {
+            themes,
+            base_dirs,
+            cache: Mutex::default(),
+            system_themes: Mutex::default(),
+            iconify_sets: Mutex::default(),
+        }
+    }
+
+    /// Finds an icon, trying each theme in order.
+    ///
+    /// `size` is in logical pixels, and `scale` is the scale factor of the
+    /// display the icon will be shown on.
+    pub fn resolve(&self, name: &str, size: u32, scale: u32) -> Option<ResolvedIcon> {
+        let key = (name.to_owned(), size, scale);
+        if let Some(cached) = self.cache.lock().get(&key) {
+            return cached.clone();
+        }
+
+        let resolved = self
+            .themes
+            .iter()
+            .find_map(|theme| match theme.kind {
+                IconThemeKind::System => self.lookup_system(&theme.name, name, size, scale),
+                IconThemeKind::IconifyIcon => self.lookup_iconify(&theme.name, name, size, scale),
+            })
+            .or_else(|| lookup_pixmap(name));
+
+        self.cache.lock().insert(key, resolved.clone());
+        resolved
+    }
+
+    fn lookup_system(
+        &self,
+        theme: &str,
+        name: &str,
+        size: u32,
+        scale: u32,
+    ) -> Option<ResolvedIcon> {
+        let mut visited = vec![];
+        let mut pending = vec![theme.to_owned()];
+
+        // depth first through the inherited themes
+        while let Some(theme_name) = pending.pop() {
+            if visited.contains(&theme_name) {
+                continue;
+            }
+            visited.push(theme_name.clone());
+
+            let Some(theme) = self.system_theme(&theme_name) else {
+                continue;
+            };
+            if let Some(path) = theme.lookup(name, size, scale) {
+                return Some(ResolvedIcon::File(path));
+            }
+            pending.extend(theme.inherits.iter().rev().cloned());
+        }
+
+        if visited.iter().any(|theme| theme == FALLBACK_THEME) {
+            return None;
+        }
+        self.system_theme(FALLBACK_THEME)?
+            .lookup(name, size, scale)
+            .map(ResolvedIcon::File)
+    }
+
+    fn lookup_iconify(
+        &self,
+        prefix: &str,
+        name: &str,
+        size: u32,
+        scale: u32,
+    ) -> Option<ResolvedIcon> {
+        let set = self
+            .iconify_sets
+            .lock()
+            .entry(prefix.to_owned())
+            .or_insert_with(|| IconifySet::load(prefix).map(Arc::new))
+            .clone()?;
+
+        set.svg(name, size * scale).map(ResolvedIcon::Svg)
+    }
+
+    fn system_theme(&self, name: &str) -> Option<Arc<SystemTheme>> {
+        self.system_themes
+            .lock()
+            .entry(name.to_owned())
+            .or_insert_with(|| SystemTheme::load(&self.base_dirs, name).map(Arc::new))
+            .clone()
+    }
+}
+
+/// Directories that icon themes are searched for in, most important first.
+fn base_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![];
+    if let Some(home) = dirs::home_dir() {
+        dirs.push(home.join(".icons"));
+    }
+    if let Some(data) = dirs::data_dir() {
+        dirs.push(data.join("icons"));
+    }
+
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| String::from("/usr/local/share:/usr/share"));
+    dirs.extend(
+        std::env::split_paths(&data_dirs)
+            .filter(|dir| dir.is_absolute())
+            .map(|dir| dir.join("icons")),
+    );
+
+    dirs
+}
+
+/// Icons that aren't in any theme.
+fn lookup_pixmap(name: &str) -> Option<ResolvedIcon> {
+    EXTENSIONS
+        .iter()
+        .map(|ext| Path::new("/usr/share/pixmaps").join(format!("{name}.{ext}")))
+        .find(|path| path.is_file())
+        .map(ResolvedIcon::File)
+}
+
+/// A system icon theme, parsed from it's `index.theme`.
+struct SystemTheme {
+    /// Folders of this theme in each base directory.
+    roots: Vec<PathBuf>,
+    directories: Vec<ThemeDirectory>,
+    inherits: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct ThemeDirectory {
+    path: String,
+    size: u32,
+    scale: u32,
+    kind: DirectoryKind,
+    min_size: u32,
+    max_size: u32,
+    threshold: u32,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum DirectoryKind {
+    Fixed,
+    Scalable,
+    #[default]
+    Threshold,
+}
+
+impl SystemTheme {
+    fn load(base_dirs: &[PathBuf], name: &str) -> Option<Self> {
+        let roots: Vec<_> = base_dirs
+            .iter()
+            .map(|dir| dir.join(name))
+            .filter(|dir| dir.is_dir())
+            .collect();
+
+        let index = roots
+            .iter()
+            .find_map(|root| std::fs::read_to_string(root.join("index.theme")).ok())?;
+
+        let theme = Self::parse(roots, &index);
+        if theme.is_none() {
+            tracing::warn!("failed to parse index.theme of icon theme {name:?}");
+        }
+        theme
+    }
+
+    fn parse(roots: Vec<PathBuf>, index: &str) -> Option<Self> {
+        let sections = parse_ini(index);
+        let main = sections.get("Icon Theme")?;
+
+        let list = |key: &str| -> Vec<String> {
+            main.get(key)
+                .map(|value| {
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                        .map(ToOwned::to_owned)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let mut paths = list("Directories");
+        paths.extend(list("ScaledDirectories"));
+
+        let directories = paths
+            .into_iter()
+            .filter_map(|path| {
+                let section = sections.get(path.as_str())?;
+                let num = |key: &str| section.get(key).and_then(|v| v.parse::<u32>().ok());
+
+                let size = num("Size")?;
+                Some(ThemeDirectory {
+                    size,
+                    scale: num("Scale").unwrap_or(1),
+                    kind: match section.get("Type").map(String::as_str) {
+                        Some("Fixed") => DirectoryKind::Fixed,
+                        Some("Scalable") => DirectoryKind::Scalable,
+                        _ => DirectoryKind::Threshold,
+                    },
+                    min_size: num("MinSize").unwrap_or(size),
+                    max_size: num("MaxSize").unwrap_or(size),
+                    threshold: num("Threshold").unwrap_or(2),
+                    path,
+                })
+            })
+            .collect();
+
+        Some(Self {
+            roots,
+            directories,
+            inherits: list("Inherits"),
+        })
+    }
+
+    /// Finds an icon in this theme only, preferring an exact size match,
+    /// then the closest size.
+    fn lookup(&self, name: &str, size: u32, scale: u32) -> Option<PathBuf> {
+        let find_in = |dir: &ThemeDirectory| {
+            self.roots.iter().find_map(|root| {
+                EXTENSIONS
+                    .iter()
+                    .map(|ext| root.join(&dir.path).join(format!("{name}.{ext}")))
+                    .find(|path| path.is_file())
+            })
+        };
+
+        if let Some(path) = self
+            .directories
+            .iter()
+            .filter(|dir| dir.matches_size(size, scale))
+            .find_map(find_in)
+        {
+            return Some(path);
+        }
+
+        let mut by_distance: Vec<_> = self.directories.iter().collect();
+        by_distance.sort_by_key(|dir| dir.size_distance(size, scale));
+        by_distance.into_iter().find_map(find_in)
+    }
+}
+
+impl ThemeDirectory {
+    fn matches_size(&self, size: u32, scale: u32) -> bool {
+        if self.scale != scale {
+            return false;
+        }
+        match self.kind {
+            DirectoryKind::Fixed => self.size == size,
+            DirectoryKind::Scalable => (self.min_size..=self.max_size).contains(&size),
+            DirectoryKind::Threshold => (self.size.saturating_sub(self.threshold)
+                ..=self.size + self.threshold)
+                .contains(&size),
+        }
+    }
+
+    fn size_distance(&self, size: u32, scale: u32) -> u32 {
+        let target = size * scale;
+        let (min, max) = match self.kind {
+            DirectoryKind::Fixed => (self.size, self.size),
+            DirectoryKind::Scalable => (self.min_size, self.max_size),
+            DirectoryKind::Threshold => (
+                self.size.saturating_sub(self.threshold),
+                self.size + self.threshold,
+            ),
+        };
+
+        if target < min * self.scale {
+            min * self.scale - target
+        } else {
+            target.saturating_sub(max * self.scale)
+        }
+    }
+}
+
+/// Parses a desktop entry style ini file into `section -> key -> value`.
+fn parse_ini(s: &str) -> HashMap<&str, HashMap<&str, String>> {
+    let mut sections: HashMap<&str, HashMap<&str, String>> = HashMap::new();
+    let mut current = None;
+
+    for line in s.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current = Some(section);
+            sections.entry(section).or_default();
+        } else if let (Some(section), Some((key, value))) = (current, line.split_once('=')) {
+            sections
+                .entry(section)
+                .or_default()
+                .insert(key.trim(), value.trim().to_owned());
+        }
+    }
+
+    sections
+}
+
+/// An Iconify icon set in JSON format.
+#[derive(Deserialize)]
+struct IconifySet {
+    icons: HashMap<String, IconifyEntry>,
+    #[serde(default)]
+    aliases: HashMap<String, IconifyAlias>,
+    #[serde(default = "default_iconify_size")]
+    width: u32,
+    #[serde(default = "default_iconify_size")]
+    height: u32,
+}
+
+#[derive(Deserialize)]
+struct IconifyEntry {
+    body: String,
+    width: Option<u32>,
+    height: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct IconifyAlias {
+    parent: String,
+}
+
+fn default_iconify_size() -> u32 {
+    16
+}
+
+impl IconifySet {
+    fn load(prefix: &str) -> Option<Self> {
+        let path = ICONIFY_DIR.join(format!("{prefix}.json"));
+        let json = std::fs::read_to_string(&path)
+            .inspect_err(|e| tracing::warn!("failed to read icon set {path:?}: {e}"))
+            .ok()?;
+        serde_json::from_str(&json)
+            .inspect_err(|e| tracing::warn!("failed to parse icon set {path:?}: {e}"))
+            .ok()
+    }
+
+    /// Renders an icon as an SVG document, `pixels` wide and tall.
+    fn svg(&self, name: &str, pixels: u32) -> Option<String> {
+        let icon = self.icons.get(name).or_else(|| {
+            let alias = self.aliases.get(name)?;
+            self.icons.get(&alias.parent)
+        })?;
+
+        let width = icon.width.unwrap_or(self.width);
+        let height = icon.height.unwrap_or(self.height);
+        Some(format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{pixels}" height="{pixels}" viewBox="0 0 {width} {height}">{}</svg>"#,
+            icon.body
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use covey_config::config::{IconTheme, IconThemeKind};
+
+    use super::{parse_ini, DirectoryKind, IconResolver, ResolvedIcon, ThemeDirectory};
+
+    /// Folder with the `child`, `parent` and `hicolor` themes.
+    fn fixtures() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/icons")
+    }
+
+    fn resolver(theme: &str) -> IconResolver {
+        IconResolver::with_base_dirs(
+            vec![IconTheme {
+                kind: IconThemeKind::System,
+                name: theme.to_owned(),
+            }],
+            vec![fixtures()],
+        )
+    }
+
+    fn file(path: &str) -> ResolvedIcon {
+        ResolvedIcon::File(fixtures().join(path))
+    }
+
+    fn directory(kind: DirectoryKind, size: u32, scale: u32) -> ThemeDirectory {
+        ThemeDirectory {
+            path: String::new(),
+            size,
+            scale,
+            kind,
+            min_size: 24,
+            max_size: 256,
+            threshold: 2,
+        }
+    }
+
+    #[test]
+    fn ini_sections_and_keys_are_parsed() {
+        let ini = parse_ini(
+            "
+            ignored = before any section
+            [Icon Theme]
+            # comment
+            Name = Test
+            Example=a=b
+
+            [16x16/apps]
+            Size=16
+            ",
+        );
+
+        assert_eq!(ini.len(), 2);
+        assert_eq!(ini["Icon Theme"]["Name"], "Test");
+        assert_eq!(ini["Icon Theme"]["Example"], "a=b");
+        assert_eq!(ini["16x16/apps"]["Size"], "16");
+        assert!(!ini["Icon Theme"].contains_key("# comment"));
+    }
+
+    #[test]
+    fn directories_match_sizes_by_kind() {
+        let fixed = directory(DirectoryKind::Fixed, 16, 1);
+        assert!(fixed.matches_size(16, 1));
+        assert!(!fixed.matches_size(17, 1));
+        assert!(!fixed.matches_size(16, 2), "scale must be the same");
+
+        let scalable = directory(DirectoryKind::Scalable, 48, 1);
+        assert!(scalable.matches_size(24, 1));
+        assert!(scalable.matches_size(256, 1));
+        assert!(!scalable.matches_size(257, 1));
+
+        let threshold = directory(DirectoryKind::Threshold, 32, 2);
+        assert!(threshold.matches_size(30, 2));
+        assert!(threshold.matches_size(34, 2));
+        assert!(!threshold.matches_size(35, 2));
+    }
+
+    #[test]
+    fn size_distance_is_in_pixels() {
+        let fixed = directory(DirectoryKind::Fixed, 16, 1);
+        assert_eq!(fixed.size_distance(16, 1), 0);
+        assert_eq!(fixed.size_distance(20, 1), 4);
+        assert_eq!(fixed.size_distance(12, 1), 4);
+        assert_eq!(fixed.size_distance(16, 2), 16);
+
+        let scalable = directory(DirectoryKind::Scalable, 48, 1);
+        assert_eq!(scalable.size_distance(100, 1), 0);
+        assert_eq!(scalable.size_distance(16, 1), 8);
+
+        let threshold = directory(DirectoryKind::Threshold, 32, 2);
+        assert_eq!(threshold.size_distance(33, 2), 0);
+        assert_eq!(threshold.size_distance(40, 2), 12);
+    }
+
+    #[test]
+    fn icons_are_found_in_the_closest_size() {
+        let resolver = resolver("child");
+        assert_eq!(
+            resolver.resolve("terminal", 16, 1),
+            Some(file("child/16x16/apps/terminal.png"))
+        );
+        assert_eq!(
+            resolver.resolve("terminal", 48, 1),
+            Some(file("child/scalable/apps/terminal.svg"))
+        );
+        assert_eq!(
+            resolver.resolve("terminal", 20, 1),
+            Some(file("child/16x16/apps/terminal.png")),
+            "the theme's own icons are used before inherited icons"
+        );
+    }
+
+    #[test]
+    fn inherited_themes_are_searched() {
+        let resolver = resolver("child");
+        assert_eq!(
+            resolver.resolve("files", 32, 1),
+            Some(file("parent/32x32/apps/files.png"))
+        );
+        assert_eq!(
+            resolver.resolve("files", 32, 2),
+            Some(file("parent/32x32@2/apps/files.png"))
+        );
+        assert_eq!(
+            resolver.resolve("calculator", 48, 1),
+            Some(file("hicolor/48x48/apps/calculator.png")),
+            "hicolor is searched last"
+        );
+        assert_eq!(
+            resolver.resolve("covey-missing-icon", 16, 1),
+            None,
+            "themes inheriting from each other don't loop"
+        );
+
+        assert_eq!(
+            self::resolver("parent").resolve("terminal", 32, 1),
+            Some(file("parent/32x32/apps/terminal.png"))
+        );
+    }
+}
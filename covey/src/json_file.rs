@@ -0,0 +1,136 @@
+//! Reading and writing data that the host keeps in [`DATA_DIR`].
+//!
+//! [`DATA_DIR`]: crate::DATA_DIR
+
+use std::{fs, io, path::Path, sync::Arc, time::Duration};
+
+use color_eyre::eyre::Result;
+use parking_lot::Mutex;
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::error;
+
+/// How long [`DelayedFile`] waits after a change before writing it.
+const WRITE_DELAY: Duration = Duration::from_secs(1);
+
+/// Reads a JSON file.
+///
+/// If the file doesn't exist, the default value is returned.
+pub(crate) fn read<T: DeserializeOwned + Default>(path: &Path) -> Result<T> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Writes JSON to a file, creating its folder if needed.
+fn write(path: &Path, json: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // write to another file first, so the file is never half written
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(&temp_path, json)?;
+    fs::rename(temp_path, path)?;
+    Ok(())
+}
+
+/// A JSON file that is written a short time after it changes, so that
+/// several changes in a row are written together.
+///
+/// The file is written on a blocking thread, so the host isn't locked
+/// while writing. Anything that hasn't been written yet is written when
+/// this is dropped.
+pub(crate) struct DelayedFile {
+    path: &'static Path,
+    /// The latest contents that haven't been written yet.
+    pending: Arc<Mutex<Option<String>>>,
+    /// Held while writing, so that older contents are never written after
+    /// newer ones.
+    writing: Arc<Mutex<()>>,
+}
+
+impl DelayedFile {
+    pub(crate) fn new(path: &'static Path) -> Self {
+        Self {
+            path,
+            pending: Arc::default(),
+            writing: Arc::default(),
+        }
+    }
+
+    /// Writes `value` to the file after [`WRITE_DELAY`], replacing
+    /// anything that hasn't been written yet.
+    ///
+    /// This must be called within a tokio runtime.
+    pub(crate) fn schedule(&self, value: &impl Serialize) {
+        let json = match serde_json::to_string(value) {
+            Ok(json) => json,
+            Err(e) => {
+                error!("error serializing {:?}: {e:#}", self.path);
+                return;
+            }
+        };
+        if self.pending.lock().replace(json).is_some() {
+            // the write that is already scheduled will write this
+            return;
+        }
+
+        let (path, pending, writing) = (
+            self.path,
+            Arc::clone(&self.pending),
+            Arc::clone(&self.writing),
+        );
+        tokio::spawn(async move {
+            tokio::time::sleep(WRITE_DELAY).await;
+            let write =
+                tokio::task::spawn_blocking(move || write_pending(path, &pending, &writing));
+            if let Err(e) = write.await {
+                error!("error writing {path:?}: {e:#}");
+            }
+        });
+    }
+}
+
+impl Drop for DelayedFile {
+    fn drop(&mut self) {
+        write_pending(self.path, &self.pending, &self.writing);
+    }
+}
+
+fn write_pending(path: &Path, pending: &Mutex<Option<String>>, writing: &Mutex<()>) {
+    let _writing = writing.lock();
+    let Some(json) = pending.lock().take() else {
+        return;
+    };
+    if let Err(e) = write(path, &json) {
+        error!("error writing {path:?}: {e:#}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use super::{read, DelayedFile};
+
+    #[test]
+    fn delayed_file_writes_the_latest_value_when_dropped() {
+        let dir = std::env::temp_dir().join(format!("covey-json-file-{}", std::process::id()));
+        let path: &'static Path = Box::leak(dir.join("data.json").into_boxed_path());
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let _runtime = runtime.enter();
+
+        let file = DelayedFile::new(path);
+        file.schedule(&1);
+        file.schedule(&2);
+        assert!(!path.exists(), "nothing is written before the delay");
+
+        drop(file);
+        assert_eq!(read::<i32>(path).unwrap(), 2);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,15 +1,28 @@
+mod commands;
 mod event;
+mod frecency;
+mod history;
 mod host;
+pub mod icons;
+mod json_file;
 mod plugin;
 mod proto;
 mod spawn;
+mod terminal;
 
 use std::{path::PathBuf, sync::LazyLock};
 
+pub use commands::ItemCommand;
 use covey_config::config::GlobalConfig;
-pub use event::{Icon, Input, List, ListItem, ListItemId, ListStyle};
+pub use event::{
+    Accessory, Confirmation, DynamicCommand, Icon, Input, List, ListItem, ListItemId, ListStyle,
+    Notification, Page, ParameterRequest, Preview, Section, TagColor, Urgency,
+};
 pub use host::Host;
 pub use plugin::Plugin;
+// only for the tauri frontend, which needs it to find its paste tool
+#[doc(hidden)]
+pub use spawn::is_in_path;
 
 pub static CONFIG_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
     dirs::config_dir()
@@ -33,12 +46,70 @@ pub trait Frontend: Send + 'static {
     /// Copy a string to the clipboard.
     fn copy(&mut self, str: String);
 
+    /// Close the window and insert a string into the window that was
+    /// focused before this frontend was shown.
+    ///
+    /// Frontends can insert the text however they like, for example by
+    /// setting the clipboard and simulating a paste, or by typing it with
+    /// a virtual keyboard. The user's clipboard should be restored
+    /// afterwards if it was changed.
+    ///
+    /// The default implementation closes the window and copies the string,
+    /// so that the user can paste it manually.
+    fn paste(&mut self, str: String) {
+        self.close();
+        self.copy(str);
+    }
+
     /// Set the UI input to the provided input.
     fn set_input(&mut self, input: Input);
 
     /// Set the UI results list to the provided list.
     fn set_list(&mut self, list: List);
 
+    /// Ask the user to confirm before activating something.
+    ///
+    /// The frontend must call [`Host::confirm`] with the user's answer.
+    /// Nothing will be run until the confirmation is accepted.
+    ///
+    /// The default implementation rejects every confirmation, by never
+    /// accepting it.
+    fn confirm(&mut self, confirmation: Confirmation) {
+        tracing::warn!("frontend can't ask for confirmation, rejecting {confirmation:?}");
+    }
+
+    /// Ask the user to enter the parameters of a command.
+    ///
+    /// The frontend must call [`Host::submit_parameters`] with the values.
+    /// The command will not be activated until valid values are submitted.
+    ///
+    /// The default implementation cancels every request, by never
+    /// submitting values.
+    fn collect_parameters(&mut self, request: ParameterRequest) {
+        tracing::warn!("frontend can't collect parameters, cancelling {request:?}");
+    }
+
+    /// Set the page that is currently open.
+    ///
+    /// [`None`] means that no page is open, and the main list should
+    /// be shown.
+    ///
+    /// The default implementation ignores pages, so the lists of pages
+    /// are shown like the main list.
+    fn set_page(&mut self, page: Option<Page>) {
+        if let Some(page) = page {
+            tracing::warn!("frontend can't show pages, ignoring {page:?}");
+        }
+    }
+
+    /// Show a desktop notification.
+    ///
+    /// Frontends that can't show notifications should leave this as the
+    /// default implementation, which only logs the notification.
+    fn notify(&mut self, notification: Notification) {
+        tracing::info!("frontend can't show notifications, ignoring {notification:?}");
+    }
+
     /// Reset the frontend with a new configuration.
     fn reload(&mut self, config: GlobalConfig);
 
@@ -1,14 +1,18 @@
 use core::fmt;
-use std::{hash::Hash, path::PathBuf, sync::Arc};
+use std::{collections::HashMap, hash::Hash, path::PathBuf, sync::Arc};
 
 use color_eyre::eyre::Result;
 use covey_config::{
     config::PluginConfig,
+    hotkey::HotkeySequence,
     keyed_list::{Id, Identify},
     manifest::PluginManifest,
 };
 
-use crate::{event::Action, proto, Input, List, DATA_DIR};
+use crate::{
+    event::{Action, CapturedCommand, Command},
+    proto, Input, List, Notification, Page, Preview, DATA_DIR,
+};
 
 /// A ref-counted reference to a plugin instance.
 ///
@@ -32,6 +36,14 @@ impl Plugin {
         })
     }
 
+    /// Creates a plugin with a manifest that has already been read.
+    #[cfg(test)]
+    pub(crate) fn with_manifest(config: PluginConfig, manifest: PluginManifest) -> Self {
+        Self {
+            plugin: Arc::new(implementation::LazyPlugin::with_manifest(config, manifest)),
+        }
+    }
+
     pub fn id(&self) -> &Id {
         &self.plugin.config.id
     }
@@ -60,13 +72,40 @@ impl Plugin {
         &self.plugin.manifest
     }
 
-    pub(crate) async fn query(&self, query: impl Into<String>) -> Result<List> {
+    /// Hotkeys that the user has set for commands, which replace the
+    /// commands' default hotkeys.
+    pub fn command_hotkeys(&self) -> &HashMap<Id, HotkeySequence> {
+        &self.plugin.config.commands
+    }
+
+    /// Whether the user must confirm before a command is activated.
+    ///
+    /// This is required if either the manifest or the user's config say so.
+    pub fn requires_confirmation(&self, command_name: &str) -> bool {
+        self.manifest()
+            .commands
+            .get(command_name)
+            .is_some_and(|command| command.confirm)
+            || self
+                .plugin
+                .config
+                .confirm_commands
+                .iter()
+                .any(|id| id.as_str() == command_name)
+    }
+
+    /// Queries the plugin's main list, or a page if `page_id` is provided.
+    pub(crate) async fn query(
+        &self,
+        query: impl Into<String>,
+        page_id: Option<u64>,
+    ) -> Result<List> {
         Ok(List::from_proto(
             self,
             self.plugin
                 .get_and_init()
                 .await?
-                .call_query(query.into())
+                .call_query(query.into(), page_id)
                 .await?,
         ))
     }
@@ -75,12 +114,49 @@ impl Plugin {
         &self,
         selection_id: u64,
         command_name: String,
+        parameters: Option<String>,
+    ) -> Result<Vec<Action>> {
+        Ok(self.map_proto_actions(
+            self.plugin
+                .get_and_init()
+                .await?
+                .call_activate(selection_id, command_name, parameters)
+                .await?,
+        ))
+    }
+
+    /// Fetches the preview of a list item.
+    ///
+    /// Returns [`None`] if the item has no preview.
+    pub(crate) async fn preview(&self, selection_id: u64) -> Result<Option<Preview>> {
+        let preview = self
+            .plugin
+            .get_and_init()
+            .await?
+            .call_preview(selection_id)
+            .await?;
+
+        Ok(preview.and_then(|preview| {
+            let preview = Preview::from_proto(preview);
+            if preview.is_none() {
+                tracing::error!("plugin {self:?} did not provide preview content: ignoring");
+            }
+            preview
+        }))
+    }
+
+    /// Sends the output of a captured command back to the plugin.
+    pub(crate) async fn handle_output(
+        &self,
+        callback_id: u64,
+        stdout: String,
+        stderr: String,
     ) -> Result<Vec<Action>> {
         Ok(self.map_proto_actions(
             self.plugin
                 .get_and_init()
                 .await?
-                .call_activate(selection_id, command_name)
+                .call_handle_output(callback_id, stdout, stderr)
                 .await?,
         ))
     }
@@ -98,12 +174,34 @@ impl Plugin {
 
                 Some(match action {
                     PAction::Close(()) => Action::Close,
-                    PAction::RunCommand(proto::Command { cmd, args }) => {
-                        Action::RunCommand(cmd, args)
+                    PAction::RunCommand(command) => {
+                        Action::RunCommand(Command::from_proto(command))
                     }
                     PAction::RunShell(str) => Action::RunShell(str),
+                    PAction::RunCaptured(captured) => {
+                        let Some(captured) = CapturedCommand::from_proto(captured) else {
+                            tracing::error!(
+                                "plugin {self:?} did not provide a program to run: ignoring"
+                            );
+                            return None;
+                        };
+                        Action::RunCaptured(captured)
+                    }
+                    PAction::RunInTerminal(terminal) => Action::RunInTerminal {
+                        hold: terminal.hold(),
+                        command: Command::from_proto(terminal.command),
+                    },
                     PAction::Copy(str) => Action::Copy(str),
-                    PAction::SetInput(input) => Action::SetInput(Input::from_proto(self, input)),
+                    PAction::Paste(str) => Action::Paste(str),
+                    PAction::SetInput(input) => Action::SetInput(Input::from_proto(input)),
+                    PAction::PushPage(page) => Action::PushPage(Page::from_proto(self, page)),
+                    PAction::Confirm(proto::Confirm { message, actions }) => Action::Confirm {
+                        message,
+                        actions: self.map_proto_actions(actions),
+                    },
+                    PAction::Notify(notification) => {
+                        Action::Notify(Notification::from_proto(notification))
+                    }
                 })
             })
             .collect()
@@ -170,7 +268,7 @@ fn manifest_path(plugin_name: &str) -> PathBuf {
 }
 
 mod implementation {
-    use std::{path::PathBuf, process::Stdio};
+    use std::{path::PathBuf, process::Stdio, time::Duration};
 
     use color_eyre::eyre::{Context as _, Result};
     use covey_config::{config::PluginConfig, manifest::PluginManifest};
@@ -180,7 +278,7 @@ mod implementation {
         sync::OnceCell,
     };
     use tonic::{transport::Channel, Request};
-    use tracing::info;
+    use tracing::{info, warn};
 
     use super::{
         binary_path, manifest_path,
@@ -212,6 +310,15 @@ mod implementation {
             })
         }
 
+        #[cfg(test)]
+        pub(super) fn with_manifest(config: PluginConfig, manifest: PluginManifest) -> Self {
+            Self {
+                cell: OnceCell::new(),
+                manifest,
+                config,
+            }
+        }
+
         /// Gets access to a plugin and ensures it is initialised.
         ///
         /// Locks exclusive access to the plugin while initialising.
@@ -242,8 +349,58 @@ mod implementation {
     /// initialised state.
     pub(super) struct PluginInner {
         plugin: PluginClient<Channel>,
-        // killed on drop, need to hold it so that it's dropped when this struct is dropped.
-        _process: Child,
+        // stopped on drop, need to hold it so that it's dropped when this struct is dropped.
+        _process: PluginProcess,
+    }
+
+    /// How long a plugin has to stop after SIGTERM before it's killed.
+    const STOP_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+    /// The server process of a plugin, which is stopped when this is
+    /// dropped.
+    ///
+    /// This is only [`None`] while being dropped.
+    struct PluginProcess(Option<Child>);
+
+    impl Drop for PluginProcess {
+        fn drop(&mut self) {
+            let Some(mut child) = self.0.take() else {
+                return;
+            };
+
+            // plugins write anything they haven't yet when they get
+            // SIGTERM, which they can't do if they're killed
+            #[cfg(unix)]
+            let terminated = child
+                .id()
+                .and_then(|pid| libc::pid_t::try_from(pid).ok())
+                // SAFETY: `kill` doesn't touch any memory.
+                .is_some_and(|pid| unsafe { libc::kill(pid, libc::SIGTERM) } == 0);
+            #[cfg(not(unix))]
+            let terminated = false;
+
+            // kill plugins that are stuck or ignore SIGTERM
+            match tokio::runtime::Handle::try_current() {
+                Ok(runtime) if terminated => {
+                    runtime.spawn(async move {
+                        if tokio::time::timeout(STOP_GRACE_PERIOD, child.wait())
+                            .await
+                            .is_err()
+                        {
+                            warn!("plugin process didn't stop after SIGTERM, killing it");
+                            if let Err(e) = child.kill().await {
+                                warn!("failed to kill plugin process: {e}");
+                            }
+                        }
+                    });
+                }
+                _ => {
+                    if let Err(e) = child.start_kill() {
+                        warn!("failed to stop plugin process: {e}");
+                    }
+                }
+            }
+        }
     }
 
     impl PluginInner {
@@ -253,12 +410,12 @@ mod implementation {
             let mut process = Command::new(&bin_path)
                 .stdout(Stdio::piped())
                 .stderr(Stdio::piped())
-                .kill_on_drop(true)
                 .spawn()
                 .context("failed to spawn plugin server")?;
 
             let stdout = process.stdout.take().expect("stdout should be captured");
             let stderr = process.stderr.take().expect("stderr should be captured");
+            let process = PluginProcess(Some(process));
             let mut stdout = BufReader::new(stdout);
             let stderr = BufReader::new(stderr);
 
@@ -304,11 +461,15 @@ mod implementation {
             Ok(())
         }
 
-        pub(super) async fn call_query(&self, query: String) -> Result<proto::QueryResponse> {
+        pub(super) async fn call_query(
+            &self,
+            query: String,
+            page_id: Option<u64>,
+        ) -> Result<proto::QueryResponse> {
             Ok(self
                 .plugin
                 .clone()
-                .query(Request::new(proto::QueryRequest { query }))
+                .query(Request::new(proto::QueryRequest { query, page_id }))
                 .await?
                 .into_inner())
         }
@@ -317,6 +478,7 @@ mod implementation {
             &self,
             selection_id: u64,
             command_name: String,
+            parameters: Option<String>,
         ) -> Result<Vec<proto::Action>> {
             Ok(self
                 .plugin
@@ -324,6 +486,39 @@ mod implementation {
                 .activate(Request::new(proto::ActivationRequest {
                     selection_id,
                     command_name,
+                    parameters,
+                }))
+                .await?
+                .into_inner()
+                .actions)
+        }
+
+        pub(super) async fn call_preview(
+            &self,
+            selection_id: u64,
+        ) -> Result<Option<proto::Preview>> {
+            Ok(self
+                .plugin
+                .clone()
+                .preview(Request::new(proto::PreviewRequest { selection_id }))
+                .await?
+                .into_inner()
+                .preview)
+        }
+
+        pub(super) async fn call_handle_output(
+            &self,
+            callback_id: u64,
+            stdout: String,
+            stderr: String,
+        ) -> Result<Vec<proto::Action>> {
+            Ok(self
+                .plugin
+                .clone()
+                .handle_output(Request::new(proto::OutputRequest {
+                    callback_id,
+                    stdout,
+                    stderr,
                 }))
                 .await?
                 .into_inner()
@@ -1,19 +1,161 @@
 //! Quick utilities for spawning processes
 
-use std::{ffi::OsStr, process::Stdio};
+use std::{collections::HashMap, ffi::OsStr, path::Path, process::Stdio};
 
 use color_eyre::eyre::Result;
 use tokio::process::Child;
 
-/// Spawn a process with `Stdio::null()` for stdin/out/err.
-pub(crate) fn free_null(
+/// Environment that a process is spawned with.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Env<'a> {
+    /// Working directory of the process.
+    ///
+    /// The process inherits covey's working directory if this is [`None`].
+    pub(crate) cwd: Option<&'a Path>,
+    /// Extra environment variables to set.
+    pub(crate) vars: Option<&'a HashMap<String, String>>,
+    /// Patterns of covey's environment variables to remove.
+    ///
+    /// See [`AppConfig::strip_env_vars`].
+    ///
+    /// [`AppConfig::strip_env_vars`]: covey_config::config::AppConfig::strip_env_vars
+    pub(crate) strip: &'a [String],
+}
+
+impl Env<'_> {
+    fn command(
+        self,
+        cmd: impl AsRef<OsStr>,
+        args: impl IntoIterator<Item: AsRef<OsStr>>,
+    ) -> tokio::process::Command {
+        let mut command = tokio::process::Command::new(cmd);
+        command.args(args);
+
+        if let Some(cwd) = self.cwd {
+            command.current_dir(cwd);
+        }
+        for (key, _) in std::env::vars_os() {
+            if is_stripped(&key, self.strip) {
+                command.env_remove(key);
+            }
+        }
+        if let Some(vars) = self.vars {
+            command.envs(vars);
+        }
+
+        command
+    }
+}
+
+/// Spawn a process with `Stdio::null()` for stdin/out/err, fully detached
+/// from covey.
+///
+/// The process is started in a new session, so it keeps running if covey
+/// exits. It is reaped in the background once it exits.
+pub(crate) fn detached(
     cmd: impl AsRef<OsStr>,
     args: impl IntoIterator<Item: AsRef<OsStr>>,
-) -> Result<Child> {
-    Ok(tokio::process::Command::new(cmd)
-        .args(args)
+    env: Env<'_>,
+) -> Result<()> {
+    let mut command = env.command(cmd, args);
+    command
         .stdin(Stdio::null())
         .stdout(Stdio::null())
-        .stderr(Stdio::null())
+        .stderr(Stdio::null());
+
+    #[cfg(unix)]
+    // SAFETY: `setsid` is async-signal-safe and doesn't touch any memory.
+    unsafe {
+        command.pre_exec(|| {
+            if libc::setsid() == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+
+    let mut child = command.spawn()?;
+    // avoid leaving a zombie process
+    tokio::spawn(async move {
+        if let Err(e) = child.wait().await {
+            tracing::warn!("failed to wait for detached process: {e}");
+        }
+    });
+
+    Ok(())
+}
+
+/// Spawn a process with `Stdio::null()` for stdin, capturing stdout/err.
+pub(crate) fn captured(
+    cmd: impl AsRef<OsStr>,
+    args: impl IntoIterator<Item: AsRef<OsStr>>,
+    env: Env<'_>,
+) -> Result<Child> {
+    Ok(env
+        .command(cmd, args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
         .spawn()?)
 }
+
+/// Whether an executable named `bin` is in one of the `$PATH` directories.
+pub fn is_in_path(bin: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(bin).is_file()))
+}
+
+/// Whether an environment variable matches any of the patterns.
+///
+/// A pattern ending in `*` matches any variable starting with the rest
+/// of the pattern.
+fn is_stripped(key: &OsStr, patterns: &[String]) -> bool {
+    let Some(key) = key.to_str() else {
+        return false;
+    };
+
+    patterns
+        .iter()
+        .any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => key.starts_with(prefix),
+            None => key == pattern,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsStr;
+
+    use super::is_stripped;
+
+    fn stripped(key: &str, patterns: &[&str]) -> bool {
+        let patterns: Vec<String> = patterns.iter().map(|p| (*p).to_owned()).collect();
+        is_stripped(OsStr::new(key), &patterns)
+    }
+
+    #[test]
+    fn exact_patterns_match_the_whole_name() {
+        assert!(stripped("RUST_LOG", &["RUST_LOG"]));
+        assert!(!stripped("RUST_LOG_STYLE", &["RUST_LOG"]));
+        assert!(!stripped("RUST", &["RUST_LOG"]));
+    }
+
+    #[test]
+    fn trailing_star_matches_a_prefix() {
+        assert!(stripped("TAURI_ENV_DEBUG", &["TAURI_*"]));
+        assert!(stripped("TAURI_", &["TAURI_*"]));
+        assert!(!stripped("TAURI", &["TAURI_*"]));
+        assert!(stripped("ANYTHING", &["*"]));
+    }
+
+    #[test]
+    fn other_variables_are_kept() {
+        assert!(!stripped("HOME", &["RUST_LOG", "TAURI_*"]));
+        assert!(!stripped("HOME", &[]));
+        assert!(
+            !stripped("rust_log", &["RUST_LOG"]),
+            "names are case sensitive"
+        );
+    }
+}
@@ -0,0 +1,190 @@
+//! Running commands in the user's terminal emulator.
+
+use std::sync::LazyLock;
+
+use color_eyre::eyre::{eyre, Context, Result};
+
+use crate::spawn::is_in_path;
+
+/// Placeholder in a terminal template that is replaced by the command.
+const PLACEHOLDER: &str = "{}";
+
+/// Terminals that are tried in order if the user hasn't set one, with
+/// their templates.
+const KNOWN_TERMINALS: &[(&str, &str)] = &[
+    ("kitty", "kitty {}"),
+    ("alacritty", "alacritty -e {}"),
+    ("foot", "foot {}"),
+    ("wezterm", "wezterm start -- {}"),
+    ("ghostty", "ghostty -e {}"),
+    ("konsole", "konsole -e {}"),
+    ("gnome-terminal", "gnome-terminal -- {}"),
+    ("xfce4-terminal", "xfce4-terminal -x {}"),
+    ("xterm", "xterm -e {}"),
+];
+
+/// Template of the detected terminal.
+///
+/// This is detected once, from `$TERMINAL` or the first known terminal
+/// that is available.
+static DETECTED: LazyLock<Option<String>> = LazyLock::new(|| {
+    let template = std::env::var("TERMINAL")
+        .ok()
+        .filter(|terminal| !terminal.trim().is_empty())
+        .map(|terminal| template_of_env_var(&terminal))
+        .or_else(|| {
+            KNOWN_TERMINALS
+                .iter()
+                .find(|(bin, _)| is_in_path(bin))
+                .map(|(_, template)| (*template).to_owned())
+        });
+    tracing::info!("detected terminal {template:?}");
+    template
+});
+
+/// Template of a terminal set by the `$TERMINAL` environment variable.
+///
+/// The variable is used as is if it has a placeholder. Otherwise, the
+/// arguments of a known terminal are used, falling back to `-e` which most
+/// terminals accept.
+fn template_of_env_var(terminal: &str) -> String {
+    if terminal.contains(PLACEHOLDER) {
+        return terminal.to_owned();
+    }
+
+    let bin = terminal.rsplit('/').next().unwrap_or(terminal);
+    match KNOWN_TERMINALS.iter().find(|(known, _)| *known == bin) {
+        Some((known, template)) => template.replacen(known, terminal, 1),
+        None => format!("{terminal} -e {PLACEHOLDER}"),
+    }
+}
+
+/// Returns the command and arguments that run `cmd` in a terminal.
+///
+/// Uses the user's `template` if there is one, otherwise a detected
+/// terminal. The template is split like a shell would, so arguments can
+/// be quoted.
+///
+/// # Errors
+/// Returns an error if there is no template and no terminal could be
+/// detected, or the template is empty or has unclosed quotes.
+pub(crate) fn command(
+    template: Option<&str>,
+    cmd: String,
+    args: Vec<String>,
+    hold: bool,
+) -> Result<(String, Vec<String>)> {
+    let template = template
+        .or(DETECTED.as_deref())
+        .ok_or_else(|| eyre!("no terminal found: set one in the app settings"))?;
+
+    let mut program = vec![cmd];
+    program.extend(args);
+    if hold {
+        program = hold_open(program);
+    }
+
+    let mut words = shell_words::split(template)
+        .context(format!("invalid terminal command `{template}`"))?
+        .into_iter();
+    let terminal = words
+        .next()
+        .ok_or_else(|| eyre!("terminal command is empty"))?;
+
+    let mut terminal_args: Vec<String> = words.collect();
+    if let Some(index) = terminal_args.iter().position(|arg| arg == PLACEHOLDER) {
+        terminal_args.splice(index..=index, program);
+    } else {
+        terminal_args.extend(program);
+    }
+
+    Ok((terminal, terminal_args))
+}
+
+/// Wraps a program so that the terminal waits for the user to press enter
+/// after it exits.
+fn hold_open(program: Vec<String>) -> Vec<String> {
+    let script =
+        r#""$@"; printf '\n[exited with status %s, press enter to close]' "$?"; read -r _"#;
+
+    ["sh", "-c", script, "sh"]
+        .into_iter()
+        .map(ToOwned::to_owned)
+        .chain(program)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{command, template_of_env_var};
+
+    fn run(template: &str, hold: bool) -> (String, Vec<String>) {
+        command(
+            Some(template),
+            "htop".to_owned(),
+            vec!["--tree".to_owned()],
+            hold,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn placeholder_is_replaced_by_the_command() {
+        assert_eq!(
+            run("wezterm start -- {} --extra", false),
+            (
+                "wezterm".to_owned(),
+                ["start", "--", "htop", "--tree", "--extra"]
+                    .map(String::from)
+                    .to_vec()
+            )
+        );
+    }
+
+    #[test]
+    fn command_is_appended_without_placeholder() {
+        assert_eq!(
+            run("kitty", false),
+            (
+                "kitty".to_owned(),
+                ["htop", "--tree"].map(String::from).to_vec()
+            )
+        );
+    }
+
+    #[test]
+    fn quoted_arguments_are_kept_together() {
+        let (terminal, args) = run(
+            r#""/opt/My Terminal/term" --title 'covey run' -e {}"#,
+            false,
+        );
+        assert_eq!(terminal, "/opt/My Terminal/term");
+        assert_eq!(args, ["--title", "covey run", "-e", "htop", "--tree"]);
+    }
+
+    #[test]
+    fn held_commands_wait_after_exiting() {
+        let (_, args) = run("alacritty -e {}", true);
+        assert_eq!(&args[..3], ["-e", "sh", "-c"]);
+        assert_eq!(&args[args.len() - 3..], ["sh", "htop", "--tree"]);
+    }
+
+    #[test]
+    fn invalid_templates_are_errors() {
+        let command = |template| command(Some(template), "htop".to_owned(), vec![], false);
+        assert!(command("").is_err());
+        assert!(command("  ").is_err());
+        assert!(command("kitty 'unclosed").is_err());
+    }
+
+    #[test]
+    fn terminal_env_var_uses_known_arguments() {
+        assert_eq!(template_of_env_var("kitty"), "kitty {}");
+        assert_eq!(
+            template_of_env_var("/usr/bin/gnome-terminal"),
+            "/usr/bin/gnome-terminal -- {}"
+        );
+        assert_eq!(template_of_env_var("st"), "st -e {}");
+        assert_eq!(template_of_env_var("foot --hold {}"), "foot --hold {}");
+    }
+}